@@ -1,7 +1,43 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use criterion::{criterion_group, criterion_main, Criterion};
 
 use routefinder::*;
 
+/// counts allocations so that benchmarks can report them alongside
+/// their timings
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// the number of allocations made while running `f`, including any
+/// made while dropping its output
+fn count_allocations<R>(f: impl FnOnce() -> R) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
 fn benchmark(c: &mut Criterion) {
     let mut router = Router::new();
     router.add("/posts/:post_id/comments/:id", 1).unwrap();
@@ -35,5 +71,97 @@ fn benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, benchmark);
+fn segment_count_pruning(c: &mut Criterion) {
+    let mut router = Router::new();
+    for depth in 1..=10 {
+        for name in 0..10 {
+            let spec = (0..depth)
+                .map(|segment| format!("/{}-{}", name, segment))
+                .collect::<String>();
+            router.add(spec, ()).unwrap();
+            router
+                .add(format!("/:param{}", "/:param".repeat(depth - 1)), ())
+                .unwrap();
+        }
+    }
+    router.add("/*", ()).unwrap();
+
+    c.bench_function("pruned /n/n/n", |b| b.iter(|| router.best_match("/1/2/3")));
+
+    c.bench_function("pruned fallthrough", |b| {
+        b.iter(|| router.best_match("/a/b/c/d/e/f/g/h/i/j/k"))
+    });
+}
+
+fn static_specs(c: &mut Criterion) {
+    let specs: Vec<&'static str> = (0..1000)
+        .map(|n| &*Box::leak(format!("/resources{}/:id/items/:item", n).into_boxed_str()))
+        .collect();
+
+    let add_static = || {
+        let mut router = Router::new();
+        for spec in &specs {
+            router
+                .add(RouteSpec::from_static(spec).unwrap(), ())
+                .unwrap();
+        }
+        router
+    };
+
+    let add_owned = || {
+        let mut router = Router::new();
+        for spec in &specs {
+            router.add(*spec, ()).unwrap();
+        }
+        router
+    };
+
+    println!(
+        "allocations adding 1000 specs: {} static, {} owned",
+        count_allocations(add_static),
+        count_allocations(add_owned)
+    );
+
+    c.bench_function("add 1000 static specs", |b| b.iter(add_static));
+    c.bench_function("add 1000 owned specs", |b| b.iter(add_owned));
+}
+
+fn batch(c: &mut Criterion) {
+    let mut router = Router::new();
+    for n in 0..100 {
+        router.add(format!("/static/{}", n), n).unwrap();
+        router.add(format!("/dynamic{}/:id", n), n).unwrap();
+    }
+    router.add("/*", 0).unwrap();
+
+    let paths: Vec<String> = (0..1000)
+        .map(|n| match n % 3 {
+            0 => format!("/static/{}", n % 100),
+            1 => format!("/dynamic{}/{}", n % 100, n),
+            _ => format!("/fallthrough/{}", n),
+        })
+        .collect();
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+
+    c.bench_function("1000 paths individually", |b| {
+        b.iter(|| {
+            paths
+                .iter()
+                .map(|path| router.best_match(path))
+                .collect::<Vec<_>>()
+        })
+    });
+
+    c.bench_function("1000 paths batched", |b| {
+        b.iter(|| router.best_match_batch(&paths))
+    });
+}
+
+criterion_group!(
+    benches,
+    benchmark,
+    segment_count_pruning,
+    static_specs,
+    batch
+);
 criterion_main!(benches);
@@ -0,0 +1,27 @@
+#![no_main]
+
+//! feeds arbitrary input to the spec parser, which must never panic,
+//! and checks that any spec it accepts is displayed as a spec that
+//! parses back to an equal spec. run with
+//! `cargo +nightly fuzz run parse_spec`
+
+use libfuzzer_sys::fuzz_target;
+use routefinder::RouteSpec;
+
+fuzz_target!(|data: &[u8]| {
+    let source = match std::str::from_utf8(data) {
+        Ok(source) => source,
+        Err(_) => return,
+    };
+
+    if let Ok(spec) = source.parse::<RouteSpec>() {
+        let displayed = spec.to_string();
+        let reparsed = displayed.parse::<RouteSpec>().unwrap_or_else(|e| {
+            panic!(
+                "{:?} displayed as {:?}, which failed: {}",
+                source, displayed, e
+            )
+        });
+        assert_eq!(spec, reparsed, "{:?} displayed as {:?}", source, displayed);
+    }
+});
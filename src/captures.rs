@@ -1,10 +1,40 @@
 use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
 
-/// Captured params and wildcards
-#[derive(Debug, Default)]
-pub struct Captures(pub(crate) Vec<(String, String)>, pub(crate) Option<String>);
+use crate::route::render_path;
+use crate::{ExtractError, RouteSpec, Segment};
+
+/// Captured params and wildcards, as well as the [`RouteSpec`] they
+/// were captured by, if any
+#[derive(Debug, Default, Clone)]
+pub struct Captures(
+    pub(crate) Vec<(String, String)>,
+    pub(crate) Option<String>,
+    pub(crate) Option<Arc<RouteSpec>>,
+);
 
 impl Captures {
+    /// pairs each param and wildcard segment of the spec with its
+    /// captured value, in order
+    pub(crate) fn new(spec: Arc<RouteSpec>, values: &[&str]) -> Self {
+        let mut captures = Self::default();
+        for (segment, value) in spec
+            .segments()
+            .iter()
+            .filter(|s| matches!(s, Segment::Param(_) | Segment::Wildcard))
+            .zip(values)
+        {
+            match segment {
+                Segment::Param(name) => captures.0.push((name.to_string(), String::from(*value))),
+                _ => captures.1 = Some(String::from(*value)),
+            }
+        }
+
+        captures.2 = Some(spec);
+        captures
+    }
+
     /// returns what the * wildcard matched, if any
     pub fn wildcard(&self) -> Option<&str> {
         self.1.as_deref()
@@ -16,6 +46,101 @@ impl Captures {
             .iter()
             .find_map(|(k, v)| if k == key { Some(&**v) } else { None })
     }
+
+    /// removes the param with this name, returning its value if it
+    /// was present
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// let mut captures = router.best_match("/users/5").unwrap().captures();
+    /// assert_eq!(captures.remove("id"), Some(String::from("5")));
+    /// assert_eq!(captures.get("id"), None);
+    /// ```
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.0.iter().position(|(k, _)| k == name)?;
+        Some(self.0.remove(index).1)
+    }
+
+    /// keeps only the params for which `f` returns true when called
+    /// with the param's name and value. the wildcard is never removed
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/:_tenant/users/:id", ()).unwrap();
+    /// let mut captures = router.best_match("/acme/users/5").unwrap().captures();
+    /// captures.retain(|name, _| !name.starts_with('_'));
+    /// assert_eq!(captures.get("_tenant"), None);
+    /// assert_eq!(captures.get("id"), Some("5"));
+    /// ```
+    pub fn retain<F: FnMut(&str, &str) -> bool>(&mut self, mut f: F) {
+        self.0.retain(|(k, v)| f(k, v));
+    }
+
+    /// returns what a named wildcard matched, split on the separator,
+    /// as in `/browse/*parts`. an empty wildcard has no segments.
+    /// returns None if there is no wildcard with this name
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/browse/*parts", ()).unwrap();
+    /// let captures = router.best_match("/browse/a/b").unwrap().captures();
+    /// assert_eq!(captures.get_segments("parts"), Some(vec!["a", "b"]));
+    /// assert_eq!(captures.get_segments("other"), None);
+    /// ```
+    pub fn get_segments(&self, name: &str) -> Option<Vec<&str>> {
+        let spec = self.spec().filter(|s| s.wildcard_name() == Some(name))?;
+        match self.wildcard().unwrap_or_default() {
+            "" => Some(vec![]),
+            wildcard => Some(wildcard.split(spec.separator()).collect()),
+        }
+    }
+
+    /// parses the param with this name, returning an [`ExtractError`]
+    /// that names the param if it is missing or fails to parse
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// let captures = router.best_match("/users/5").unwrap().captures();
+    /// assert_eq!(captures.parse::<u32>("id").unwrap(), 5);
+    /// assert!(captures.parse::<u32>("name").is_err());
+    /// ```
+    pub fn parse<F: FromStr>(&self, name: &str) -> Result<F, ExtractError> {
+        let value = self.get(name).ok_or_else(|| ExtractError::Missing {
+            name: String::from(name),
+        })?;
+
+        value.parse().map_err(|_| ExtractError::Parse {
+            name: String::from(name),
+            value: String::from(value),
+            expected: std::any::type_name::<F>(),
+        })
+    }
+
+    /// the [`RouteSpec`] that produced these captures, if they came
+    /// from a [`Match`](crate::Match)
+    pub fn spec(&self) -> Option<&RouteSpec> {
+        self.2.as_deref()
+    }
+
+    /// renders the [`RouteSpec`] that produced these captures back
+    /// into a path, substituting each captured param and wildcard.
+    /// returns None if there is no spec or a param is missing
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id/*", ()).unwrap();
+    /// let captures = router.best_match("/users/5/posts/1").unwrap().captures();
+    /// assert_eq!(captures.to_path().unwrap(), "/users/5/posts/1");
+    /// ```
+    pub fn to_path(&self) -> Option<String> {
+        let spec = self.spec()?;
+        render_path(spec.segments(), spec.separator(), |segment| match segment {
+            Segment::Param(name) => self.get(name),
+            _ => Some(self.wildcard().unwrap_or_default()),
+        })
+    }
 }
 
 impl Deref for Captures {
@@ -0,0 +1,116 @@
+use std::cmp::Ordering;
+
+use crate::{Match, Router};
+
+/// How a [`RouterChain`] chooses between matches from different
+/// routers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChainPolicy {
+    /// the best match from the first router that has any match wins,
+    /// even if a later router has a more specific route. this is the
+    /// default
+    #[default]
+    FirstRouterWins,
+    /// the best match across all routers wins, according to the
+    /// precedence settings of the router with the best match so far,
+    /// such as [`Router::set_wildcard_outranks_param`]. if the best
+    /// matches from two routers have equal precedence, or precedence
+    /// is by insertion order, the earlier router wins
+    GlobalBest,
+}
+
+/// An ordered list of [`Router`]s that are applied to a path as if
+/// they were one router, such as when each plugin in a system owns
+/// its own router. See [`ChainPolicy`] for how matches from different
+/// routers are chosen between.
+pub struct RouterChain<T> {
+    routers: Vec<Router<T>>,
+    policy: ChainPolicy,
+}
+
+impl<T> std::fmt::Debug for RouterChain<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouterChain")
+            .field("routers", &self.routers)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<T> Default for RouterChain<T> {
+    fn default() -> Self {
+        Self {
+            routers: Vec::new(),
+            policy: ChainPolicy::default(),
+        }
+    }
+}
+
+impl<T> RouterChain<T> {
+    /// Builds a new, empty chain with the
+    /// [`ChainPolicy::FirstRouterWins`] policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a new, empty chain with the provided policy
+    pub fn with_policy(policy: ChainPolicy) -> Self {
+        Self {
+            routers: Vec::new(),
+            policy,
+        }
+    }
+
+    /// Sets the policy for choosing between matches from different
+    /// routers
+    pub fn set_policy(&mut self, policy: ChainPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns the current [`ChainPolicy`]
+    pub fn policy(&self) -> ChainPolicy {
+        self.policy
+    }
+
+    /// Appends a router to the end of the chain
+    pub fn push(&mut self, router: Router<T>) {
+        self.routers.push(router);
+    }
+
+    /// The routers in this chain, in order
+    pub fn routers(&self) -> &[Router<T>] {
+        &self.routers[..]
+    }
+
+    /// Returns the single best match across the chained routers,
+    /// according to the [`ChainPolicy`].
+    ///
+    /// ```rust
+    /// use routefinder::{ChainPolicy, Router, RouterChain};
+    /// let mut plugin_a = Router::new();
+    /// plugin_a.add("/*", "a").unwrap();
+    /// let mut plugin_b = Router::new();
+    /// plugin_b.add("/hello", "b").unwrap();
+    ///
+    /// let mut chain = RouterChain::new();
+    /// chain.push(plugin_a);
+    /// chain.push(plugin_b);
+    /// assert_eq!(*chain.best_match("/hello").unwrap().handler(), "a");
+    ///
+    /// chain.set_policy(ChainPolicy::GlobalBest);
+    /// assert_eq!(*chain.best_match("/hello").unwrap().handler(), "b");
+    /// ```
+    pub fn best_match<'a, 'b>(&'a self, path: &'b str) -> Option<Match<'a, 'b, T>> {
+        let mut matches = self.routers.iter().filter_map(|r| r.best_match(path));
+        match self.policy {
+            ChainPolicy::FirstRouterWins => matches.next(),
+            ChainPolicy::GlobalBest => matches.reduce(|best, m| {
+                if best.precedence_cmp(&m) == Ordering::Less {
+                    m
+                } else {
+                    best
+                }
+            }),
+        }
+    }
+}
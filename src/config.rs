@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::Router;
+
+/// A single declarative route, as it might appear in a TOML or JSON
+/// routes file. The `handler_key` is looked up in the handler map
+/// passed to [`Router::from_config`].
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct RouteConfig {
+    /// the route spec, such as `/users/:id`
+    pub path: String,
+    /// the key of the handler for this route
+    pub handler_key: String,
+}
+
+impl<T: Clone> Router<T> {
+    /// Builds a router from a list of [`RouteConfig`]s, cloning each
+    /// route's handler out of `handlers` by its `handler_key`. Every
+    /// entry is attempted, and if any route fails to parse or refers
+    /// to a handler key that isn't in `handlers`, all of the errors
+    /// are returned together, in the order of `entries`.
+    ///
+    /// ```rust
+    /// use routefinder::{RouteConfig, Router};
+    /// use std::collections::HashMap;
+    ///
+    /// let entries: Vec<RouteConfig> = serde_json::from_str(r#"[
+    ///     { "path": "/users/:id", "handler_key": "user" },
+    ///     { "path": "*", "handler_key": "not_found" }
+    /// ]"#).unwrap();
+    ///
+    /// let mut handlers = HashMap::new();
+    /// handlers.insert(String::from("user"), 1);
+    /// handlers.insert(String::from("not_found"), 404);
+    ///
+    /// let router = Router::from_config(entries, &handlers).unwrap();
+    /// assert_eq!(*router.best_match("/users/1").unwrap().handler(), 1);
+    /// assert_eq!(*router.best_match("/elsewhere").unwrap().handler(), 404);
+    /// ```
+    pub fn from_config(
+        entries: Vec<RouteConfig>,
+        handlers: &HashMap<String, T>,
+    ) -> Result<Self, Vec<String>> {
+        Self::from_routes(
+            entries
+                .into_iter()
+                .map(
+                    |RouteConfig { path, handler_key }| match handlers.get(&handler_key) {
+                        Some(handler) => Ok((path, handler.clone())),
+                        None => Err(format!(
+                            "no handler found for key `{}` (route `{}`)",
+                            handler_key, path
+                        )),
+                    },
+                ),
+        )
+    }
+}
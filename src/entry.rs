@@ -0,0 +1,79 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, BuildHasherDefault};
+
+use crate::{Route, RouteSpec, Router};
+
+/// A view into a single route spec in a [`Router`], which may or may
+/// not have a handler yet. Built with [`Router::entry`].
+pub struct Entry<'router, T, S = BuildHasherDefault<DefaultHasher>> {
+    router: &'router mut Router<T, S>,
+    spec: RouteSpec,
+    index: Option<usize>,
+}
+
+impl<'router, T, S: BuildHasher> std::fmt::Debug for Entry<'router, T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entry")
+            .field("spec", &self.spec)
+            .field("occupied", &self.is_occupied())
+            .finish()
+    }
+}
+
+impl<'router, T, S: BuildHasher> Entry<'router, T, S> {
+    pub(crate) fn new(
+        router: &'router mut Router<T, S>,
+        spec: RouteSpec,
+        index: Option<usize>,
+    ) -> Self {
+        Self {
+            router,
+            spec,
+            index,
+        }
+    }
+
+    /// the [`RouteSpec`] for this entry
+    pub fn spec(&self) -> &RouteSpec {
+        &self.spec
+    }
+
+    /// whether the router already contains a route with this spec
+    pub fn is_occupied(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Adds the route with this handler if it is not already in the
+    /// router, and returns a mutable reference to the route's handler
+    pub fn or_insert(self, handler: T) -> &'router mut T {
+        self.or_insert_with(|| handler)
+    }
+
+    /// Adds the route with the handler returned by `f` if it is not
+    /// already in the router, and returns a mutable reference to the
+    /// route's handler. `f` is not called if the route exists.
+    pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> &'router mut T {
+        let Self {
+            router,
+            spec,
+            index,
+        } = self;
+
+        let index = match index {
+            Some(index) => index,
+            None => router.insert(Route::from_spec(spec, f())),
+        };
+
+        router.route_mut(index).handler_mut()
+    }
+
+    /// Calls `f` with the existing handler if the route is already in
+    /// the router, and returns the entry for further chaining
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        if let Some(index) = self.index {
+            f(self.router.route_mut(index).handler_mut());
+        }
+
+        self
+    }
+}
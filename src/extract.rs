@@ -0,0 +1,130 @@
+use std::fmt;
+
+use std::sync::Arc;
+
+use crate::{Captures, Match, Segment};
+
+/// A type that can be built from the [`Captures`] of a match, for use
+/// with [`Match::extract`](crate::Match::extract). There is no derive
+/// for this trait; implementations will usually call
+/// [`Captures::parse`] for each field.
+///
+/// ```rust
+/// use routefinder::{Captures, ExtractError, FromCaptures, Router};
+///
+/// struct Post {
+///     user: String,
+///     id: u32,
+/// }
+///
+/// impl FromCaptures for Post {
+///     fn from_captures(captures: &Captures) -> Result<Self, ExtractError> {
+///         Ok(Self {
+///             user: captures.parse("user")?,
+///             id: captures.parse("id")?,
+///         })
+///     }
+/// }
+///
+/// let mut router = Router::new();
+/// router.add("/:user/posts/:id", ()).unwrap();
+/// let post: Post = router.best_match("/jane/posts/5").unwrap().extract().unwrap();
+/// assert_eq!(post.user, "jane");
+/// assert_eq!(post.id, 5);
+/// ```
+pub trait FromCaptures: Sized {
+    /// build this type from the captures, or describe which capture
+    /// was missing or invalid
+    fn from_captures(captures: &Captures) -> Result<Self, ExtractError>;
+
+    /// replace `out` with a value built from a match, for use with
+    /// [`Router::best_match_into`](crate::Router::best_match_into).
+    /// implement this to reuse the allocations of `out`, such as by
+    /// clearing and refilling its strings from the borrowed
+    /// [`Match::named_captures`], so that no allocation is needed for
+    /// each match. by default, this replaces `out` with
+    /// [`FromCaptures::from_captures`], which builds the match's
+    /// [`Captures`], and leaves `out` unchanged on error
+    fn from_match_into<T>(m: &Match<'_, '_, T>, out: &mut Self) -> Result<(), ExtractError> {
+        *out = m.extract()?;
+        Ok(())
+    }
+}
+
+/// The reason a [`FromCaptures`] type could not be built from a
+/// match's [`Captures`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractError {
+    /// the route has no param with this name
+    Missing {
+        /// the name of the param
+        name: String,
+    },
+    /// the captured value could not be parsed as the expected type
+    Parse {
+        /// the name of the param
+        name: String,
+        /// the captured value
+        value: String,
+        /// the name of the type the value should have parsed as
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::Missing { name } => write!(f, "missing param `{}`", name),
+            ExtractError::Parse {
+                name,
+                value,
+                expected,
+            } => write!(
+                f,
+                "could not parse param `{}` value `{}` as {}",
+                name, value, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
+impl FromCaptures for Captures {
+    fn from_captures(captures: &Captures) -> Result<Self, ExtractError> {
+        Ok(captures.clone())
+    }
+
+    /// refills the params of `out` in place, reusing the strings it
+    /// already has
+    fn from_match_into<T>(m: &Match<'_, '_, T>, out: &mut Self) -> Result<(), ExtractError> {
+        let mut len = 0;
+        for (name, value) in m.named_captures() {
+            match out.0.get_mut(len) {
+                Some((n, v)) => {
+                    n.clear();
+                    n.push_str(name);
+                    v.clear();
+                    v.push_str(value);
+                }
+                None => out.0.push((String::from(name), String::from(value))),
+            }
+            len += 1;
+        }
+        out.0.truncate(len);
+
+        match (m.route().segments().last(), &mut out.1) {
+            (Some(Segment::Wildcard), Some(wildcard)) => {
+                wildcard.clear();
+                wildcard.push_str(m.wildcard().unwrap_or_default());
+            }
+            (Some(Segment::Wildcard), wildcard) => {
+                *wildcard = Some(String::from(m.wildcard().unwrap_or_default()))
+            }
+            (_, wildcard) => *wildcard = None,
+        }
+
+        out.2 = Some(Arc::clone(m.route().captured_spec()));
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use crate::Captures;
+
+/// An optional trait for handlers that can be invoked directly with
+/// the [`Captures`] of a match, for use with
+/// [`Router::dispatch_call`](crate::Router::dispatch_call). It is
+/// implemented for any `Fn(&Captures) -> R`.
+///
+/// Routers do not require their handlers to implement this trait;
+/// [`Router::best_match`](crate::Router::best_match) remains
+/// available for any handler type.
+pub trait Handler {
+    /// the value returned when this handler is called
+    type Output;
+
+    /// invoke this handler with the captures from a match
+    fn call(&self, captures: &Captures) -> Self::Output;
+}
+
+impl<F, R> Handler for F
+where
+    F: Fn(&Captures) -> R,
+{
+    type Output = R;
+
+    fn call(&self, captures: &Captures) -> R {
+        self(captures)
+    }
+}
@@ -7,13 +7,32 @@
 )]
 
 mod captures;
+mod chain;
+#[cfg(feature = "serde")]
+mod config;
+mod entry;
+mod extract;
+mod handler;
+mod match_result;
 mod matches;
 mod route;
 mod router;
 mod segment;
+mod typed;
 
 pub use captures::Captures;
+pub use chain::{ChainPolicy, RouterChain};
+#[cfg(feature = "serde")]
+pub use config::RouteConfig;
+pub use entry::Entry;
+pub use extract::{ExtractError, FromCaptures};
+pub use handler::Handler;
+pub use match_result::{MatchResult, RejectionReason};
 pub use matches::{Match, Matches};
 pub use route::{Route, RouteSpec};
-pub use router::Router;
-pub use segment::Segment;
+pub use router::{MatchMetrics, Precedence, Router};
+pub use segment::{Segment, SegmentDiff};
+pub use typed::{FromParams, TypedRoute};
+
+#[doc(hidden)]
+pub use typed::param_count as __param_count;
@@ -0,0 +1,31 @@
+use crate::Match;
+
+/// The outcome of [`Router::match_result`](crate::Router::match_result),
+/// which distinguishes a path that no route has the shape of from a
+/// path that a route would have matched if not for one of its params
+#[derive(Debug)]
+pub enum MatchResult<'router, 'path, T> {
+    /// the [`Router::best_match`](crate::Router::best_match) for the path
+    Matched(Match<'router, 'path, T>),
+    /// no route matched, but the path has the shape of a route whose
+    /// param was rejected, such as a route added with
+    /// [`Router::add_typed`](crate::Router::add_typed) whose param
+    /// failed to parse as its type. this describes the highest
+    /// precedence such route
+    PathKnownButRejected(RejectionReason<'router, 'path>),
+    /// no route has the shape of the path
+    NotFound,
+}
+
+/// The param that caused a route to reject a path, as reported by
+/// [`MatchResult::PathKnownButRejected`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RejectionReason<'router, 'path> {
+    /// the name of the param
+    pub param: &'router str,
+    /// the value captured for the param
+    pub value: &'path str,
+    /// the constraint the value failed, which for a typed route is the
+    /// name of the param's type
+    pub constraint: &'static str,
+}
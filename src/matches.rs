@@ -1,11 +1,33 @@
 use std::cmp::Ordering;
 use std::collections::BTreeSet;
 use std::ops::Deref;
+use std::sync::Arc;
 
-use crate::{Captures, Route, Segment};
+use crate::route::{prefixed_segments, render_path, MatchOptions, RouteOrder};
+use crate::{
+    Captures, ExtractError, FromCaptures, FromParams, Precedence, RejectionReason, Route,
+    RouteSpec, Segment,
+};
 
 /// A set of all [`Match`]es. Most likely, you'll want to dereference
-/// this to its inner [`std::collections::BTreeSet`].
+/// this to its inner [`std::collections::BTreeSet`], or consume it
+/// with [`IntoIterator`] to get owned [`Match`]es, which are yielded
+/// from lowest to highest precedence.
+///
+/// `Matches` borrows the router for `'router` and the path for
+/// `'path`, independently. Neither borrow is tied to the `Matches`
+/// value itself, so the path only needs to outlive the matches (and
+/// anything derived from them), and a `Matches` or its owned
+/// [`Match`]es can be held while iterating or passed along lazily:
+///
+/// ```rust
+/// let mut router = routefinder::Router::new();
+/// router.add("/*", 1).unwrap();
+/// router.add("/:param", 2).unwrap();
+/// let path = String::from("/hello");
+/// let matches: Vec<_> = router.matches(&path).into_iter().collect();
+/// assert_eq!(matches.iter().map(|m| *m.handler()).collect::<Vec<_>>(), vec![1, 2]);
+/// ```
 #[derive(Debug)]
 pub struct Matches<'router, 'path, T> {
     matches: BTreeSet<Match<'router, 'path, T>>,
@@ -19,13 +41,92 @@ impl<'router, 'path, T> Deref for Matches<'router, 'path, T> {
     }
 }
 
+impl<'router, 'path, T> IntoIterator for Matches<'router, 'path, T> {
+    type Item = Match<'router, 'path, T>;
+    type IntoIter = std::collections::btree_set::IntoIter<Match<'router, 'path, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.matches.into_iter()
+    }
+}
+
 impl<'router, 'path, T> Matches<'router, 'path, T> {
     pub fn for_routes_and_path(
         routes: impl Iterator<Item = &'router Route<T>>,
         path: &'path str,
+    ) -> Self {
+        Self::for_routes_path_and_options(
+            routes,
+            path,
+            None,
+            &MatchOptions::default(),
+            RouteOrder::default(),
+        )
+    }
+
+    pub(crate) fn for_routes_path_and_options(
+        routes: impl Iterator<Item = &'router Route<T>>,
+        path: &'path str,
+        base: Option<&'router RouteSpec>,
+        options: &MatchOptions,
+        order: RouteOrder,
     ) -> Self {
         Self {
-            matches: routes.filter_map(|route| route.is_match(path)).collect(),
+            matches: routes
+                .filter_map(|route| route.is_match_with(path, base, options, order))
+                .collect(),
+        }
+    }
+}
+
+/// the most captures a [`Match`] holds without allocating
+const INLINE_CAPTURES: usize = 8;
+
+/// the params and wildcard captured by a [`Match`], in order. these
+/// are kept inline unless a route (with its base path) has more than
+/// [`INLINE_CAPTURES`] of them, so that matching doesn't allocate for
+/// typical routes
+#[derive(Debug, Clone)]
+pub(crate) enum CapturedValues<'path> {
+    Inline {
+        values: [&'path str; INLINE_CAPTURES],
+        len: usize,
+    },
+    Heap(Vec<&'path str>),
+}
+
+impl<'path> CapturedValues<'path> {
+    pub(crate) fn new() -> Self {
+        CapturedValues::Inline {
+            values: [""; INLINE_CAPTURES],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, value: &'path str) {
+        match self {
+            CapturedValues::Inline { values, len } if *len < INLINE_CAPTURES => {
+                values[*len] = value;
+                *len += 1;
+            }
+            CapturedValues::Inline { values, .. } => {
+                let mut heap = Vec::with_capacity(INLINE_CAPTURES * 2);
+                heap.extend_from_slice(values);
+                heap.push(value);
+                *self = CapturedValues::Heap(heap);
+            }
+            CapturedValues::Heap(values) => values.push(value),
+        }
+    }
+}
+
+impl<'path> Deref for CapturedValues<'path> {
+    type Target = [&'path str];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            CapturedValues::Inline { values, len } => &values[..*len],
+            CapturedValues::Heap(values) => values,
         }
     }
 }
@@ -37,49 +138,386 @@ impl<'router, 'path, T> Matches<'router, 'path, T> {
 pub struct Match<'router, 'path, T> {
     path: &'path str,
     route: &'router Route<T>,
-    captures: Vec<&'path str>,
+    base: Option<&'router RouteSpec>,
+    captures: CapturedValues<'path>,
+    fully_consumed: bool,
+    order: RouteOrder,
 }
 
 impl<'router, 'path, T> Match<'router, 'path, T> {
     pub(crate) fn new(
         path: &'path str,
         route: &'router Route<T>,
-        captures: Vec<&'path str>,
+        base: Option<&'router RouteSpec>,
+        captures: CapturedValues<'path>,
+        order: RouteOrder,
     ) -> Self {
         Self {
             path,
             route,
+            base,
             captures,
+            fully_consumed: true,
+            order,
         }
     }
 
+    /// marks this as a match for only part of the input, as with
+    /// [`Router::best_partial`](crate::Router::best_partial)
+    pub(crate) fn with_remainder(mut self, remainder: &str) -> Self {
+        self.fully_consumed = remainder.is_empty();
+        self
+    }
+
+    /// Returns false if this match came from
+    /// [`Router::best_partial`](crate::Router::best_partial) and left
+    /// part of the input unmatched. Every other match consumes the
+    /// whole path, apart from any trailing slashes, and returns true.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/deploy", ()).unwrap();
+    /// assert!(router.best_match("/deploy").unwrap().fully_consumed());
+    /// let (m, _) = router.best_partial("/deploy prod").unwrap();
+    /// assert!(!m.fully_consumed());
+    /// ```
+    pub fn fully_consumed(&self) -> bool {
+        self.fully_consumed
+    }
+
     /// Returns a reference to the handler associated with this route
     pub fn handler(&self) -> &'router T {
         self.route.handler()
     }
 
-    /// Returns the [`Captures`] for this match
-    pub fn captures(&self) -> Captures {
+    /// Returns the [`Route`] that matched
+    pub fn route(&self) -> &'router Route<T> {
         self.route
+    }
+
+    /// Returns true if the matched route was added with
+    /// [`Router::add_deprecated`](crate::Router::add_deprecated)
+    pub fn is_deprecated(&self) -> bool {
+        self.route.is_deprecated()
+    }
+
+    /// Returns the portion of the path consumed by the segments
+    /// before the wildcard, without a trailing slash. For a route
+    /// without a wildcard, this is the whole path, without trailing
+    /// slashes. When the wildcard is not empty and the path has no
+    /// trailing or doubled slashes, the prefix, a slash, and
+    /// [`Captures::wildcard`] reconstruct the original path. An empty
+    /// wildcard only leaves the prefix, so `/service-a` and
+    /// `/service-a/` both have the prefix `/service-a`.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/service-a/*", ()).unwrap();
+    /// let m = router.best_match("/service-a/some/path").unwrap();
+    /// assert_eq!(m.matched_prefix(), "/service-a");
+    /// assert_eq!(m.captures().wildcard(), Some("some/path"));
+    /// ```
+    pub fn matched_prefix(&self) -> &'path str {
+        let separator = self.route.definition().separator();
+        let path = self.path.trim_end_matches(separator);
+        match self.route.segments().last() {
+            Some(Segment::Wildcard) => {
+                let wildcard = self.captures.last().map_or(0, |w| w.len());
+                path[..path.len() - wildcard].trim_end_matches(separator)
+            }
+            _ => path,
+        }
+    }
+
+    /// what the wildcard matched, if the route has one
+    pub(crate) fn wildcard(&self) -> Option<&'path str> {
+        match self.route.segments().last() {
+            Some(Segment::Wildcard) => self.captures.last().copied(),
+            _ => None,
+        }
+    }
+
+    /// Returns the wildcard capture split into its segments on the
+    /// route's separator, or None if the route has no wildcard. An
+    /// empty wildcard has no segments.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/docs/*", ()).unwrap();
+    /// let m = router.best_match("/docs/guide/routing").unwrap();
+    /// assert_eq!(m.wildcard_segments(), Some(vec!["guide", "routing"]));
+    /// ```
+    pub fn wildcard_segments(&self) -> Option<Vec<&'path str>> {
+        match self.route.segments().last() {
+            Some(Segment::Wildcard) => {
+                let wildcard = self.captures.last().copied().unwrap_or_default();
+                if wildcard.is_empty() {
+                    Some(vec![])
+                } else {
+                    Some(
+                        wildcard
+                            .split(self.route.definition().separator())
+                            .collect(),
+                    )
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a human-readable description of why one of these two
+    /// matches takes precedence over the other, such as
+    /// `segment 1 Exact > Param`, naming the first segment at which
+    /// they differ. The segment is the position in the path, counted
+    /// from zero between separators as in [`Route::segment_count`],
+    /// rather than the index into [`RouteSpec::segments`]. This is
+    /// intended for debugging surprising route precedence. The
+    /// explanation follows the precedence settings of the router this
+    /// match came from, such as
+    /// [`Router::set_wildcard_outranks_param`](crate::Router::set_wildcard_outranks_param).
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// router.add("/*", ()).unwrap();
+    /// let matches = router.matches("/users/1");
+    /// let wildcard = matches.iter().next().unwrap();
+    /// let best = router.best_match("/users/1").unwrap();
+    /// assert_eq!(
+    ///     best.rank_explanation(wildcard),
+    ///     "/users/:id beat /* because segment 0 Exact > Wildcard"
+    /// );
+    /// ```
+    pub fn rank_explanation(&self, other: &Match<'_, '_, T>) -> String {
+        let mine = self.route.definition();
+        let theirs = other.route.definition();
+        let equal = || {
+            format!(
+                "{} and {} have equal precedence, so the first one added wins",
+                mine, theirs
+            )
+        };
+
+        if self.order.precedence == Precedence::InsertionOrder {
+            return match self.route.order().cmp(&other.route.order()) {
+                Ordering::Less => format!("{} beat {} because it was added first", mine, theirs),
+                Ordering::Greater => format!("{} beat {} because it was added first", theirs, mine),
+                Ordering::Equal => equal(),
+            };
+        }
+
+        let wildcard_outranks_param = self.order.wildcard_outranks_param;
+        let first_difference = mine
             .segments()
             .iter()
+            .zip(theirs.segments())
+            .enumerate()
+            .map(|(index, (m, t))| (index, m, t, m.precedence_cmp(t, wildcard_outranks_param)))
+            .find(|(_, _, _, ordering)| *ordering != Ordering::Equal);
+
+        // the index into the segment list counts slashes and dots, so
+        // report how many separators precede the difference instead
+        let path_segment = |index: usize| {
+            mine.segments()[..index]
+                .iter()
+                .filter(|s| **s == Segment::Slash)
+                .count()
+        };
+
+        match first_difference {
+            Some((index, m, t, Ordering::Greater)) => format!(
+                "{} beat {} because segment {} {} > {}",
+                mine,
+                theirs,
+                path_segment(index),
+                m.kind(),
+                t.kind()
+            ),
+
+            Some((index, m, t, _)) => format!(
+                "{} beat {} because segment {} {} > {}",
+                theirs,
+                mine,
+                path_segment(index),
+                t.kind(),
+                m.kind()
+            ),
+
+            None => match mine.segments().len().cmp(&theirs.segments().len()) {
+                Ordering::Less => format!("{} beat {} because it has fewer segments", mine, theirs),
+                Ordering::Greater => {
+                    format!("{} beat {} because it has fewer segments", theirs, mine)
+                }
+                Ordering::Equal => equal(),
+            },
+        }
+    }
+
+    /// compares the precedence of the matched routes according to the
+    /// router this match came from, without regard to when they were
+    /// added, so that matches from different routers can be compared
+    pub(crate) fn precedence_cmp(&self, other: &Match<'_, '_, T>) -> Ordering {
+        self.order
+            .compare_specs(self.route.definition(), other.route.definition())
+    }
+
+    /// Returns the canonical form of the matched path, rendered from
+    /// the route (including any base path) with each param and
+    /// wildcard replaced by its captured value. Exact segments use the
+    /// route's casing, even if the router is case insensitive, and
+    /// the result never has a trailing slash, since the router
+    /// ignores trailing slashes.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.set_case_insensitive(true);
+    /// router.add("/users/:id", ()).unwrap();
+    /// let m = router.best_match("/Users/5/").unwrap();
+    /// assert_eq!(m.canonical_path(), "/users/5");
+    /// ```
+    pub fn canonical_path(&self) -> String {
+        let mut captures = self.captures.iter();
+        render_path(
+            prefixed_segments(self.base, self.route.segments()),
+            self.route.definition().separator(),
+            |_| Some(captures.next().copied().unwrap_or_default()),
+        )
+        .unwrap_or_default()
+    }
+
+    /// Returns true if the matched route (including any base path)
+    /// has no params or wildcard, and the path is literally that
+    /// route, ignoring leading and trailing slashes as matching does.
+    /// A case-insensitive match with different casing is not an
+    /// exact match. This does not allocate.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/about", ()).unwrap();
+    /// router.add("/users/:id", ()).unwrap();
+    /// assert!(router.best_match("/about/").unwrap().is_exact_match());
+    /// assert!(!router.best_match("/users/1").unwrap().is_exact_match());
+    /// ```
+    pub fn is_exact_match(&self) -> bool {
+        let separator = self.route.definition().separator();
+        let mut rest = self
+            .path
+            .trim_start_matches(separator)
+            .trim_end_matches(separator);
+
+        for segment in prefixed_segments(self.base, self.route.segments()) {
+            let literal = match segment {
+                Segment::Slash => rest.strip_prefix(separator),
+                Segment::Dot => rest.strip_prefix('.'),
+                Segment::Exact(e) => rest.strip_prefix(&**e),
+                Segment::Param(_) | Segment::Wildcard => None,
+            };
+
+            match literal {
+                Some(after) => rest = after,
+                None => return false,
+            }
+        }
+
+        rest.is_empty()
+    }
+
+    /// Returns the [`Captures`] for this match. The captures also
+    /// share the matched route's [`RouteSpec`], including any base
+    /// path, so they can be rendered back into a path with
+    /// [`Captures::to_path`]
+    pub fn captures(&self) -> Captures {
+        Captures::new(Arc::clone(self.route.captured_spec()), &self.captures)
+    }
+
+    /// Builds a [`FromCaptures`] type from the [`Captures`] for this
+    /// match, returning an [`ExtractError`] naming the param that was
+    /// missing or could not be parsed
+    pub fn extract<P: FromCaptures>(&self) -> Result<P, ExtractError> {
+        P::from_captures(&self.captures())
+    }
+
+    /// Parses the route's params, in order, as the tuple of types `P`,
+    /// as with [`TypedRoute::is_match`](crate::TypedRoute::is_match).
+    /// Params in the router's base path are not included, so that `P`
+    /// is the same as for the [`TypedRoute`](crate::TypedRoute). Returns
+    /// None if the number of params differs from the number of types
+    /// or any param fails to parse.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id/posts/:slug", ()).unwrap();
+    /// let m = router.best_match("/users/5/posts/hello").unwrap();
+    /// assert_eq!(m.params(), Some((5u64, String::from("hello"))));
+    /// assert_eq!(m.params::<(u64,)>(), None);
+    /// ```
+    pub fn params<P: FromParams>(&self) -> Option<P> {
+        let params = self.route_params();
+        if params.len() == P::ARITY {
+            P::from_params(&params)
+        } else {
+            None
+        }
+    }
+
+    /// the values of the route's own params, in order, without those
+    /// in the base path or the wildcard
+    pub(crate) fn route_params(&self) -> CapturedValues<'path> {
+        let mut params = CapturedValues::new();
+        for (_, value) in self.route_named_params() {
+            params.push(value);
+        }
+        params
+    }
+
+    /// the route's own params, as with [`Match::named_captures`]
+    /// without those in the base path
+    fn route_named_params(&self) -> impl Iterator<Item = (&'router str, &'path str)> + '_ {
+        let base_params = self.base.map_or(0, |base| {
+            base.segments()
+                .iter()
+                .filter(|s| matches!(s, Segment::Param(_)))
+                .count()
+        });
+        self.named_captures().skip(base_params)
+    }
+
+    /// describes the route's own param at `index` as rejected by
+    /// `constraint`
+    pub(crate) fn rejection(
+        &self,
+        index: usize,
+        constraint: &'static str,
+    ) -> Option<RejectionReason<'router, 'path>> {
+        let (param, value) = self.route_named_params().nth(index)?;
+        Some(RejectionReason {
+            param,
+            value,
+            constraint,
+        })
+    }
+
+    /// Returns the name and captured value of each param, including
+    /// any in the base path, without allocating. The names are
+    /// borrowed from the router and the values from the path, so the
+    /// pairs can outlive this `Match`, though the iterator itself
+    /// borrows it. The wildcard is not included, even if it is named.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id/posts/:post", ()).unwrap();
+    /// let m = router.best_match("/users/5/posts/1").unwrap();
+    /// let pairs: Vec<_> = m.named_captures().collect();
+    /// assert_eq!(pairs, vec![("id", "5"), ("post", "1")]);
+    /// ```
+    pub fn named_captures(&self) -> impl Iterator<Item = (&'router str, &'path str)> + '_ {
+        prefixed_segments(self.base, self.route.segments())
             .filter(|s| matches!(s, Segment::Param(_) | Segment::Wildcard))
-            .zip(&self.captures)
-            .fold(
-                Captures::default(),
-                |mut captures, (segment, capture)| match segment {
-                    Segment::Param(name) => {
-                        captures.0.push((name.clone(), String::from(*capture)));
-                        captures
-                    }
-
-                    Segment::Wildcard => {
-                        captures.1 = Some(String::from(*capture));
-                        captures
-                    }
-                    _ => captures,
-                },
-            )
+            .zip(self.captures.iter().copied())
+            .filter_map(|(segment, capture)| match segment {
+                Segment::Param(name) => Some((&**name, capture)),
+                _ => None,
+            })
     }
 }
 
@@ -99,6 +537,6 @@ impl<'router, 'path, T> PartialOrd for Match<'router, 'path, T> {
 
 impl<'router, 'path, T> Ord for Match<'router, 'path, T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.route.cmp(&other.route)
+        self.route.cmp(other.route)
     }
 }
@@ -1,13 +1,184 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::convert::{TryFrom, TryInto};
+use std::fmt::Write;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use crate::{Match, Segment};
+use crate::matches::CapturedValues;
+use crate::{Captures, Match, Precedence, RejectionReason, Segment, SegmentDiff};
+
+/// options that change how a [`Route`] is applied to a path. these
+/// are configured on the [`Router`](crate::Router)
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MatchOptions {
+    /// whether a param may capture an empty string, as in `/users//posts`
+    pub(crate) allow_empty_params: bool,
+    /// whether exact segments ignore ascii case, so that `/Users`
+    /// matches `/users`
+    pub(crate) case_insensitive: bool,
+    /// whether a wildcard must match exactly one non-empty segment,
+    /// instead of any number of segments
+    pub(crate) strict_segment_count: bool,
+}
+
+/// the settings that determine the order of a router's routes
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RouteOrder {
+    pub(crate) precedence: Precedence,
+    pub(crate) wildcard_outranks_param: bool,
+}
+
+impl RouteOrder {
+    /// orders routes from lowest to highest precedence. earlier
+    /// routes outrank later routes of equal precedence
+    pub(crate) fn compare<T>(&self, a: &Route<T>, b: &Route<T>) -> Ordering {
+        self.compare_specs(a.definition(), b.definition())
+            .then(b.order().cmp(&a.order()))
+    }
+
+    /// compares two specs without regard to when their routes were
+    /// added, which is always Equal for [`Precedence::InsertionOrder`]
+    pub(crate) fn compare_specs(&self, a: &RouteSpec, b: &RouteSpec) -> Ordering {
+        match self.precedence {
+            Precedence::Structural => a.precedence_cmp(b, self.wildcard_outranks_param),
+            Precedence::InsertionOrder => Ordering::Equal,
+        }
+    }
+}
+
+/// checks the params of a route, returning the index of the first
+/// rejected param and the name of the constraint that rejected it
+pub(crate) type ParamCheck = fn(&[&str]) -> Option<(usize, &'static str)>;
+
+/// the segments of the `base` spec, if provided, followed by a slash
+/// and then `segments`
+pub(crate) fn prefixed_segments<'a>(
+    base: Option<&'a RouteSpec>,
+    segments: &'a [Segment],
+) -> impl Iterator<Item = &'a Segment> {
+    base.into_iter()
+        .flat_map(|base| base.segments.iter().chain(std::iter::once(&Segment::Slash)))
+        .chain(segments)
+}
+
+/// renders segments as a path with a leading separator and no
+/// trailing separator, replacing each param and wildcard with the
+/// value returned by `capture`. returns None if `capture` returns None
+/// for any segment
+pub(crate) fn render_path<'s, 'c>(
+    segments: impl IntoIterator<Item = &'s Segment>,
+    separator: char,
+    mut capture: impl FnMut(&'s Segment) -> Option<&'c str>,
+) -> Option<String> {
+    let mut path = String::from(separator);
+    for segment in segments {
+        match segment {
+            Segment::Slash => path.push(separator),
+            Segment::Dot => path.push('.'),
+            Segment::Exact(e) => path.push_str(e),
+            Segment::Param(_) | Segment::Wildcard => path.push_str(capture(segment)?),
+        }
+    }
+
+    while path.len() > separator.len_utf8() && path.ends_with(separator) {
+        path.pop();
+    }
+
+    Some(path)
+}
+
+/// removes the backslashes from an exact section of a spec, so that
+/// `\:` and `\*` are a literal colon and asterisk and `\\` is a
+/// literal backslash. any other escaped character is just that
+/// character, and a trailing backslash is kept as-is
+fn unescape(section: &str) -> String {
+    let mut unescaped = String::with_capacity(section.len());
+    let mut chars = section.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next().unwrap_or('\\')),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// splits a spec into segments and the name of its wildcard, if it
+/// has one, using `text` to turn literals and param names from the
+/// source into segment text
+fn parse_sections<'s>(
+    source: &'s str,
+    separator: char,
+    text: impl Fn(&'s str) -> Cow<'static, str>,
+) -> Result<(Vec<Segment>, Option<String>), String> {
+    let mut wildcard_name = None;
+    let segments = source
+        .trim_start_matches(separator)
+        .trim_end_matches(separator)
+        .split(separator)
+        .try_fold(vec![], |mut acc, section| {
+            let segment = match (section.chars().next(), section.len()) {
+                (Some('*'), 1) => Some(Segment::Wildcard),
+                (Some('*'), _) => {
+                    wildcard_name = Some(String::from(&section[1..]));
+                    Some(Segment::Wildcard)
+                }
+                (Some(':'), 1) => return Err(String::from("params must be named")),
+                (Some(':'), _) => Some(Segment::Param(text(&section[1..]))),
+                (None, 0) => None,
+                (_, _) if section.contains('\\') => {
+                    Some(Segment::Exact(Cow::Owned(unescape(section))))
+                }
+                (_, _) => Some(Segment::Exact(text(section))),
+            };
+            if let Some(segment) = segment {
+                if !acc.is_empty() {
+                    acc.push(Segment::Slash);
+                }
+                acc.push(segment);
+            }
+            Ok(acc)
+        })?;
+
+    match segments.iter().position(|s| *s == Segment::Wildcard) {
+        Some(index) if index + 1 < segments.len() => Err(format!(
+            "a wildcard must be the last segment of a route, but `{}` continues after it",
+            source
+        )),
+        _ => Ok((segments, wildcard_name)),
+    }
+}
+
+/// the number of separated segments in a path, ignoring leading and
+/// trailing separators. with a `/` separator, `/` has zero segments
+/// and `/a/b` has two
+pub(crate) fn path_segment_count(path: &str, separator: char) -> usize {
+    let path = path
+        .trim_start_matches(separator)
+        .trim_end_matches(separator);
+    if path.is_empty() {
+        0
+    } else {
+        path.matches(separator).count() + 1
+    }
+}
 
 /// A parsed [`RouteSpec`] and associated handler
 pub struct Route<T> {
-    definition: RouteSpec,
+    definition: Arc<RouteSpec>,
+    /// the definition prefixed by the router's base path, built when
+    /// the base path is set so that [`Captures`] can share it. when
+    /// there is no base path, captures share `definition` instead
+    prefixed: Option<Arc<RouteSpec>>,
     handler: T,
+    order: usize,
+    deprecated: bool,
+    /// for a route added with
+    /// [`Router::add_typed`](crate::Router::add_typed), checks that
+    /// the route's own params parse as its types, as with
+    /// [`FromParams::rejected_param`](crate::FromParams::rejected_param)
+    param_check: Option<ParamCheck>,
 }
 
 impl<T> std::fmt::Debug for Route<T> {
@@ -37,14 +208,82 @@ impl<T> Ord for Route<T> {
 }
 
 impl<T> Route<T> {
-    pub(crate) fn new<R>(route: R, handler: T) -> Result<Self, <R as TryInto<RouteSpec>>::Error>
+    /// builds a route outside of any [`Router`](crate::Router), which
+    /// can be applied to paths directly with [`Route::is_match`]
+    ///
+    /// ```rust
+    /// let route = routefinder::Route::new("/users/:id", ()).unwrap();
+    /// assert!(route.is_match("/users/1").is_some());
+    /// ```
+    pub fn new<R>(route: R, handler: T) -> Result<Self, <R as TryInto<RouteSpec>>::Error>
     where
         R: TryInto<RouteSpec>,
     {
-        Ok(Self {
-            definition: route.try_into()?,
+        Ok(Self::from_spec(route.try_into()?, handler))
+    }
+
+    pub(crate) fn from_spec(definition: RouteSpec, handler: T) -> Self {
+        Self {
+            definition: Arc::new(definition),
+            prefixed: None,
             handler,
-        })
+            order: 0,
+            deprecated: false,
+            param_check: None,
+        }
+    }
+
+    /// marks this route as deprecated, as with
+    /// [`Router::add_deprecated`](crate::Router::add_deprecated)
+    pub(crate) fn deprecate(mut self) -> Self {
+        self.deprecated = true;
+        self
+    }
+
+    /// only matches paths whose params pass `check`, as with
+    /// [`Router::add_typed`](crate::Router::add_typed)
+    pub(crate) fn with_param_check(mut self, check: ParamCheck) -> Self {
+        self.param_check = Some(check);
+        self
+    }
+
+    /// whether this route was added with
+    /// [`Router::add_deprecated`](crate::Router::add_deprecated)
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated
+    }
+
+    /// the position of this route in the order routes were added to
+    /// its router
+    pub(crate) fn order(&self) -> usize {
+        self.order
+    }
+
+    pub(crate) fn set_order(&mut self, order: usize) {
+        self.order = order;
+    }
+
+    /// renames the param named `old` to `new`, returning whether this
+    /// route had a param named `old`
+    ///
+    /// ```rust
+    /// let mut route = routefinder::Route::new("/users/:id", ()).unwrap();
+    /// assert!(route.rename_param("id", "user_id"));
+    /// assert_eq!(route.definition().to_string(), "/users/:user_id");
+    /// assert!(!route.rename_param("id", "user_id"));
+    /// ```
+    pub fn rename_param(&mut self, old: &str, new: &str) -> bool {
+        if !Arc::make_mut(&mut self.definition).rename_param(0, old, new) {
+            return false;
+        }
+
+        if let Some(prefixed) = &mut self.prefixed {
+            let prefixed = Arc::make_mut(prefixed);
+            let start = prefixed.segments.len() - self.definition.segments.len();
+            prefixed.rename_param(start, old, new);
+        }
+
+        true
     }
 
     /// the [`RouteSpec`] for this [`Route`]
@@ -57,39 +296,445 @@ impl<T> Route<T> {
         &self.handler
     }
 
+    /// replaces the definition, keeping the same base path as before
+    pub(crate) fn set_definition(&mut self, definition: RouteSpec, base: Option<&RouteSpec>) {
+        self.definition = Arc::new(definition);
+        self.set_base(base);
+    }
+
+    /// rebuilds the spec shared with [`Captures`] for the router's
+    /// base path
+    pub(crate) fn set_base(&mut self, base: Option<&RouteSpec>) {
+        self.prefixed = base.map(|base| Arc::new(self.definition.with_base(base)));
+    }
+
+    /// the definition prefixed by the router's base path, as reported
+    /// by [`Captures::spec`]
+    pub(crate) fn captured_spec(&self) -> &Arc<RouteSpec> {
+        self.prefixed.as_ref().unwrap_or(&self.definition)
+    }
+
+    pub(crate) fn handler_mut(&mut self) -> &mut T {
+        &mut self.handler
+    }
+
     /// a slice of [`RouteSpec`] [`Segments`] that represents this route
     pub fn segments(&self) -> &[Segment] {
-        &self.definition.segments[..]
+        self.definition.segments()
+    }
+
+    /// the number of slash-separated segments in this route, where a
+    /// wildcard counts as one segment. `/` has zero segments and
+    /// `/users/:id` has two. a route without a wildcard only matches
+    /// paths with exactly this many segments, and a route ending in a
+    /// wildcard matches paths with at least one fewer segment, since
+    /// the wildcard can match nothing
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// let m = router.best_match("/users/1").unwrap();
+    /// assert_eq!(m.route().segment_count(), 2);
+    /// ```
+    pub fn segment_count(&self) -> usize {
+        self.definition.segment_count()
+    }
+
+    /// a cheap test of whether this route could possibly match a path
+    /// with `count` segments, used to skip routes before attempting
+    /// the full match
+    pub(crate) fn accepts_segment_count(&self, count: usize) -> bool {
+        let segment_count = self.segment_count();
+        match self.segments().last() {
+            Some(Segment::Wildcard) => count + 1 >= segment_count,
+            _ => count == segment_count,
+        }
+    }
+
+    /// whether this path is literally this route, rather than just
+    /// matching its pattern. this is only ever true for a route with
+    /// no params or wildcard, and the path must be identical to the
+    /// route, with a leading separator and no trailing separator
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/list", ()).unwrap();
+    /// let route = router.best_match("/users/list").unwrap().route();
+    /// assert!(route.matches_exactly("/users/list"));
+    /// assert!(!route.matches_exactly("/users/list/"));
+    /// ```
+    pub fn matches_exactly(&self, path: &str) -> bool {
+        render_path(self.segments(), self.definition.separator(), |_| None)
+            .is_some_and(|literal| literal == path)
     }
 
-    /// performs the test of whether this route matches a given path str
+    /// performs the test of whether this route matches a given path
+    /// str. params must capture at least one character, so
+    /// `/users/:id/posts` does not match `/users//posts`
     pub fn is_match<'a, 'b>(&'a self, path: &'b str) -> Option<Match<'a, 'b, T>> {
-        let mut p = path.trim_start_matches('/').trim_end_matches('/');
-        let mut captures = vec![];
+        self.is_match_with(path, None, &MatchOptions::default(), RouteOrder::default())
+    }
+
+    /// applies this route to the path, after first matching the
+    /// `base` spec as a prefix if one is provided. the match is ranked
+    /// against other matches by `order`
+    pub(crate) fn is_match_with<'a, 'b>(
+        &'a self,
+        path: &'b str,
+        base: Option<&'a RouteSpec>,
+        options: &MatchOptions,
+        order: RouteOrder,
+    ) -> Option<Match<'a, 'b, T>> {
+        self.try_match_with(path, base, options, order).ok()
+    }
+
+    /// [`Route::is_match_with`], except that when the path has the
+    /// shape of this route but a param fails its check, the error
+    /// describes that param
+    pub(crate) fn try_match_with<'a, 'b>(
+        &'a self,
+        path: &'b str,
+        base: Option<&'a RouteSpec>,
+        options: &MatchOptions,
+        order: RouteOrder,
+    ) -> Result<Match<'a, 'b, T>, Option<RejectionReason<'a, 'b>>> {
+        let mut captures = CapturedValues::new();
+        self.definition
+            .apply(path, base, options, |capture| captures.push(capture))
+            .ok_or(None)?;
+        let m = Match::new(path, self, base, captures, order);
+        match self.param_check.and_then(|check| check(&m.route_params())) {
+            Some((index, constraint)) => Err(m.rejection(index, constraint)),
+            None => Ok(m),
+        }
+    }
+
+    /// whether this route matches the path, as with
+    /// [`Route::is_match_with`], without building a [`Match`] unless
+    /// its params need to be checked
+    pub(crate) fn matches_path(
+        &self,
+        path: &str,
+        base: Option<&RouteSpec>,
+        options: &MatchOptions,
+    ) -> bool {
+        match self.param_check {
+            Some(_) => self
+                .is_match_with(path, base, options, RouteOrder::default())
+                .is_some(),
+            None => self.definition.apply(path, base, options, |_| {}).is_some(),
+        }
+    }
+}
 
-        let mut peek = self.definition.segments.iter().peekable();
+/// the internal representation of a route, containing both the source
+/// string (or unique description), a Vec of [`Segment`]s, and the
+/// separator character that [`Segment::Slash`] represents, which is
+/// `/` unless otherwise specified, as well as the name of the
+/// wildcard, if it has one. two specs are equal if they have the same
+/// segments, separator, and wildcard name, even if their source
+/// strings differ, as with `/users/` and `users`
+#[derive(Clone)]
+pub struct RouteSpec {
+    source: Cow<'static, str>,
+    segments: Vec<Segment>,
+    separator: char,
+    wildcard_name: Option<String>,
+}
+
+impl PartialEq for RouteSpec {
+    fn eq(&self, other: &Self) -> bool {
+        self.segments == other.segments
+            && self.separator == other.separator
+            && self.wildcard_name == other.wildcard_name
+    }
+}
+
+impl Eq for RouteSpec {}
+
+impl std::fmt::Display for RouteSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_char(self.separator)?;
+        for segment in &self.segments {
+            match segment {
+                Segment::Slash => f.write_char(self.separator)?,
+                Segment::Dot => f.write_str(".")?,
+                Segment::Exact(s) => {
+                    for c in s.chars() {
+                        if matches!(c, '\\' | ':' | '*') {
+                            f.write_char('\\')?;
+                        }
+                        f.write_char(c)?;
+                    }
+                }
+                Segment::Param(p) => f.write_fmt(format_args!(":{}", p))?,
+                Segment::Wildcard => {
+                    f.write_str("*")?;
+                    if let Some(name) = &self.wildcard_name {
+                        f.write_str(name)?;
+                    }
+                }
+            };
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for RouteSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}", self))
+    }
+}
+
+impl RouteSpec {
+    pub fn new(source: &str, segments: Vec<Segment>) -> Self {
+        Self::with_separator(source, segments, '/')
+    }
+
+    /// builds a spec from segments where [`Segment::Slash`] represents
+    /// `separator` instead of `/`
+    pub fn with_separator(source: &str, segments: Vec<Segment>, separator: char) -> Self {
+        Self {
+            source: Cow::Owned(String::from(source)),
+            segments,
+            separator,
+            wildcard_name: None,
+        }
+    }
+
+    /// parses a spec in which sections are separated by `separator`
+    /// instead of `/`. paths matched against the resulting spec are
+    /// also split on `separator`
+    ///
+    /// ```rust
+    /// use routefinder::{RouteSpec, Segment};
+    /// let spec = RouteSpec::parse_with_separator("api.users.:id", '.').unwrap();
+    /// assert_eq!(spec.separator(), '.');
+    /// assert_eq!(spec.segments()[4], Segment::Param("id".into()));
+    /// assert_eq!(spec.to_string(), ".api.users.:id");
+    /// ```
+    pub fn parse_with_separator(source: &str, separator: char) -> Result<Self, String> {
+        let (segments, wildcard_name) =
+            parse_sections(source, separator, |s| Cow::Owned(String::from(s)))?;
+        Ok(
+            RouteSpec::with_separator(source, segments, separator)
+                .with_wildcard_name(wildcard_name),
+        )
+    }
+
+    /// parses a spec like [`str::parse`], except that the source and
+    /// the text of exact segments and params borrow from the
+    /// `'static` source rather than being copied, which avoids
+    /// allocating for specs written as string literals
+    ///
+    /// ```rust
+    /// use routefinder::{RouteSpec, Router};
+    /// let mut router = Router::new();
+    /// router.add(RouteSpec::from_static("/users/:id").unwrap(), ()).unwrap();
+    /// assert!(router.best_match("/users/1").is_some());
+    /// ```
+    pub fn from_static(source: &'static str) -> Result<Self, String> {
+        let separator = '/';
+        let (segments, wildcard_name) = parse_sections(source, separator, Cow::Borrowed)?;
+        Ok(Self {
+            source: Cow::Borrowed(source),
+            segments,
+            separator,
+            wildcard_name,
+        })
+    }
+
+    /// this spec, prefixed by the `base` spec and a slash
+    pub(crate) fn with_base(&self, base: &RouteSpec) -> RouteSpec {
+        RouteSpec::with_separator(
+            &format!("{}{}", base, self),
+            prefixed_segments(Some(base), &self.segments)
+                .cloned()
+                .collect(),
+            self.separator,
+        )
+        .with_wildcard_name(self.wildcard_name.clone())
+    }
+
+    /// renames the first param named `old` at or after the segment
+    /// at index `start`, returning whether there was one
+    fn rename_param(&mut self, start: usize, old: &str, new: &str) -> bool {
+        let param = self.segments[start..]
+            .iter_mut()
+            .find_map(|segment| match segment {
+                Segment::Param(name) if name == old => Some(name),
+                _ => None,
+            });
+
+        match param {
+            Some(name) => {
+                *name = Cow::Owned(String::from(new));
+                self.source = Cow::Owned(self.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// names the wildcard, so that its segments can be retrieved with
+    /// [`Captures::get_segments`]
+    pub(crate) fn with_wildcard_name(mut self, wildcard_name: Option<String>) -> Self {
+        self.wildcard_name = wildcard_name;
+        self
+    }
+
+    /// the name of the wildcard, as in `/browse/*parts`, if it has one
+    pub fn wildcard_name(&self) -> Option<&str> {
+        self.wildcard_name.as_deref()
+    }
+
+    /// the character that separates sections of this spec and of the
+    /// paths it matches
+    pub fn separator(&self) -> char {
+        self.separator
+    }
+
+    /// compares the precedence of two specs, which unlike [`Ord`] is
+    /// Equal when neither spec outranks the other. if
+    /// `wildcard_outranks_param` is true, a wildcard beats a param at
+    /// the same position instead of the other way around
+    pub(crate) fn precedence_cmp(&self, other: &Self, wildcard_outranks_param: bool) -> Ordering {
+        self.segments
+            .iter()
+            .zip(&other.segments)
+            .map(|(mine, theirs)| mine.precedence_cmp(theirs, wildcard_outranks_param))
+            .chain(std::iter::once_with(|| {
+                other.segments.len().cmp(&self.segments.len())
+            }))
+            .find(|c| *c != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// replaces the `from` prefix of this spec with `to`, if this spec
+    /// starts with all of the segments of `from` followed by a
+    /// separator or the end of the spec
+    pub(crate) fn replace_prefix(&self, from: &RouteSpec, to: &RouteSpec) -> Option<RouteSpec> {
+        if self.separator != from.separator || !self.segments.starts_with(&from.segments) {
+            return None;
+        }
+
+        let rest = match &self.segments[from.segments.len()..] {
+            [Segment::Slash, rest @ ..] => rest,
+            [] => &[],
+            rest if from.segments.is_empty() => rest,
+            _ => return None,
+        };
+
+        let mut segments = to.segments.clone();
+        if !segments.is_empty() && !rest.is_empty() {
+            segments.push(Segment::Slash);
+        }
+        segments.extend_from_slice(rest);
+
+        let mut spec = RouteSpec::with_separator("", segments, self.separator)
+            .with_wildcard_name(self.wildcard_name.clone());
+        spec.source = Cow::Owned(spec.to_string());
+        Some(spec)
+    }
+
+    /// see [`Route::segment_count`]. separators inside of
+    /// [`Segment::Exact`] literals are also counted
+    pub(crate) fn segment_count(&self) -> usize {
+        if self.segments.is_empty() {
+            return 0;
+        }
+
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Slash => 1,
+                Segment::Exact(e) => e.matches(self.separator).count(),
+                _ => 0,
+            })
+            .sum::<usize>()
+            + 1
+    }
+
+    /// the string this spec was parsed from, or the description
+    /// provided to [`RouteSpec::new`]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// a slice of the [`Segment`]s that make up this spec
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments[..]
+    }
+
+    /// applies this spec to the path, returning the captured params
+    /// and wildcard if it matches. this is the same as
+    /// [`Route::is_match`], without needing a [`Route`] or handler
+    ///
+    /// ```rust
+    /// use routefinder::RouteSpec;
+    /// let spec: RouteSpec = "/users/:id/*".parse().unwrap();
+    /// let captures = spec.try_capture("/users/5/posts/1").unwrap();
+    /// assert_eq!(captures.get("id"), Some("5"));
+    /// assert_eq!(captures.wildcard(), Some("posts/1"));
+    /// assert!(spec.try_capture("/posts/1").is_none());
+    /// ```
+    pub fn try_capture(&self, path: &str) -> Option<Captures> {
+        let mut values = vec![];
+        self.apply(path, None, &MatchOptions::default(), |value| {
+            values.push(value)
+        })?;
+        Some(Captures::new(Arc::new(self.clone()), &values))
+    }
+
+    /// the matching logic underneath [`RouteSpec::try_capture`] and
+    /// [`Route::is_match`], which calls
+    /// `capture` with each param and wildcard capture in order and
+    /// returns None as soon as the path is known not to match.
+    /// because it doesn't collect anything itself, this can be used
+    /// to test for a match without allocating
+    pub(crate) fn apply<'b>(
+        &self,
+        path: &'b str,
+        base: Option<&RouteSpec>,
+        options: &MatchOptions,
+        mut capture: impl FnMut(&'b str),
+    ) -> Option<()> {
+        let separator = self.separator;
+        let mut p = path
+            .trim_start_matches(separator)
+            .trim_end_matches(separator);
+
+        let mut peek = prefixed_segments(base, &self.segments).peekable();
         while let Some(segment) = peek.next() {
-            p = match &*segment {
-                Segment::Exact(e) => {
-                    if p.starts_with(&*e) {
+            p = match segment {
+                Segment::Exact(e) => match p.get(..e.len()) {
+                    Some(start)
+                        if start == e
+                            || (options.case_insensitive && start.eq_ignore_ascii_case(e)) =>
+                    {
                         &p[e.len()..]
-                    } else {
-                        return None;
                     }
-                }
+                    _ => return None,
+                },
 
                 Segment::Param(_) => {
                     if p.is_empty() { return None; }
                     match peek.peek() {
                         None | Some(Segment::Slash) => {
-                            let capture = p.split('/').next()?;
-                            captures.push(capture);
-                            &p[capture.len()..]
+                            let param = p.split(separator).next()?;
+                            if param.is_empty() && !options.allow_empty_params {
+                                return None;
+                            }
+                            capture(param);
+                            &p[param.len()..]
                         }
                         Some(Segment::Dot) => {
-                            let index = p.find(|c| c == '.' || c == '/')?;
-                            if p.chars().nth(index) == Some('.') {
-                                captures.push(&p[..index]);
+                            let index = p.find(['.', separator])?;
+                            if index == 0 && !options.allow_empty_params {
+                                return None;
+                            }
+                            if p[index..].starts_with('.') {
+                                capture(&p[..index]);
                                 &p[index + 1..]
                             } else {
                                 return None;
@@ -103,14 +748,17 @@ impl<T> Route<T> {
                     match peek.peek() {
                         Some(_) => panic!("wildcard must currently be the terminal segment, please file an issue if you have a use case for a mid-route *"),
                         None => {
-                            captures.push(p);
+                            if options.strict_segment_count && (p.is_empty() || p.contains(separator)) {
+                                return None;
+                            }
+                            capture(p);
                             ""
                         }
                     }
                 }
 
                 Segment::Slash => match (p.chars().next(), peek.peek()) {
-                    (Some('/'),Some(_)) => &p[1..],
+                    (Some(c), Some(_)) if c == separator => &p[c.len_utf8()..],
                     (None, None) => p,
                     (None, Some(Segment::Wildcard)) => p,
                     _ => return None,
@@ -123,50 +771,76 @@ impl<T> Route<T> {
             }
         }
 
-        if p.is_empty() || p == "/" {
-            Some(Match::new(path, &self, captures))
+        if p.is_empty() || p.strip_prefix(separator) == Some("") {
+            Some(())
         } else {
             None
         }
     }
-}
 
-/// the internal representation of a route, containing both the source
-/// string (or unique description) and a Vec of [`Segment`]s
-#[derive(PartialEq, Eq)]
-pub struct RouteSpec {
-    source: String,
-    segments: Vec<Segment>,
-}
-
-impl std::fmt::Display for RouteSpec {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("/")?;
-        for segment in &self.segments {
-            match segment {
-                Segment::Slash => f.write_str("/")?,
-                Segment::Dot => f.write_str(".")?,
-                Segment::Exact(s) => f.write_str(&s)?,
-                Segment::Param(p) => f.write_fmt(format_args!(":{}", p))?,
-                Segment::Wildcard => f.write_str("*")?,
-            };
-        }
-        Ok(())
-    }
-}
-
-impl std::fmt::Debug for RouteSpec {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{}", self))
+    /// the name of each param in this spec, along with its index in
+    /// [`RouteSpec::segments`], in order
+    ///
+    /// ```rust
+    /// use routefinder::{RouteSpec, Segment};
+    /// let spec: RouteSpec = "/users/:id".parse().unwrap();
+    /// let (name, index) = &spec.param_positions()[0];
+    /// assert_eq!(spec.segments()[*index], Segment::Param(name.clone().into()));
+    /// ```
+    pub fn param_positions(&self) -> Vec<(String, usize)> {
+        self.segments
+            .iter()
+            .enumerate()
+            .filter_map(|(index, segment)| match segment {
+                Segment::Param(name) => Some((name.to_string(), index)),
+                _ => None,
+            })
+            .collect()
     }
-}
 
-impl RouteSpec {
-    pub fn new(source: &str, segments: Vec<Segment>) -> Self {
-        Self {
-            source: String::from(source),
-            segments,
-        }
+    /// compares this spec to another, position by position, and
+    /// returns a [`SegmentDiff`] for every segment that differs. if
+    /// the specs are different lengths, the trailing segments are
+    /// reported as added or removed. an empty Vec means the specs
+    /// have identical segments
+    ///
+    /// ```rust
+    /// use routefinder::{RouteSpec, Segment, SegmentDiff};
+    /// let old: RouteSpec = "/users/:id".parse().unwrap();
+    /// let new: RouteSpec = "/users/:id/profile".parse().unwrap();
+    /// assert_eq!(
+    ///     old.diff(&new),
+    ///     vec![
+    ///         SegmentDiff::Added { index: 3, segment: Segment::Slash },
+    ///         SegmentDiff::Added {
+    ///             index: 4,
+    ///             segment: Segment::Exact("profile".into())
+    ///         },
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff(&self, other: &RouteSpec) -> Vec<SegmentDiff> {
+        let len = self.segments.len().max(other.segments.len());
+        (0..len)
+            .filter_map(
+                |index| match (self.segments.get(index), other.segments.get(index)) {
+                    (Some(from), Some(to)) if from != to => Some(SegmentDiff::Changed {
+                        index,
+                        from: from.clone(),
+                        to: to.clone(),
+                    }),
+                    (None, Some(segment)) => Some(SegmentDiff::Added {
+                        index,
+                        segment: segment.clone(),
+                    }),
+                    (Some(segment), None) => Some(SegmentDiff::Removed {
+                        index,
+                        segment: segment.clone(),
+                    }),
+                    _ => None,
+                },
+            )
+            .collect()
     }
 }
 
@@ -174,27 +848,7 @@ impl FromStr for RouteSpec {
     type Err = String;
 
     fn from_str(source: &str) -> Result<Self, Self::Err> {
-        let segments = source
-            .trim_start_matches('/')
-            .trim_end_matches('/')
-            .split('/')
-            .try_fold(vec![], |mut acc, section| {
-                let segment =                 match (section.chars().next(), section.len()) {
-                    (Some('*'), 1) => Some(Segment::Wildcard),
-                    (Some('*'), _) => return Err(format!("since there can only be one wildcard, it doesn't need a name. replace `{}` with `*`", section)),
-                    (Some(':'), 1) => return Err(String::from("params must be named")),
-                    (Some(':'), _) => Some(Segment::Param(String::from(&section[1..]))),
-                    (None, 0) => None,
-                    (_, _) => Some(Segment::Exact(String::from(section))),
-                };
-                if let Some(segment) = segment {
-                    if !acc.is_empty() {acc.push(Segment::Slash); }
-                    acc.push(segment);
-                }
-                Ok(acc)
-            })?;
-
-        Ok(RouteSpec::new(source, segments))
+        Self::parse_with_separator(source, '/')
     }
 }
 
@@ -206,10 +860,25 @@ impl TryFrom<&str> for RouteSpec {
     }
 }
 
+/// a borrowed spec is parsed with [`RouteSpec::from_static`], and an
+/// owned spec as with a [`String`]
+impl TryFrom<Cow<'static, str>> for RouteSpec {
+    type Error = String;
+
+    fn try_from(s: Cow<'static, str>) -> Result<Self, Self::Error> {
+        match s {
+            Cow::Borrowed(s) => Self::from_static(s),
+            Cow::Owned(s) => Self::try_from(s),
+        }
+    }
+}
+
 impl TryFrom<String> for RouteSpec {
     type Error = String;
     fn try_from(s: String) -> Result<Self, Self::Error> {
-        s.parse()
+        let mut spec: Self = s.parse()?;
+        spec.source = Cow::Owned(s);
+        Ok(spec)
     }
 }
 
@@ -221,14 +890,6 @@ impl PartialOrd for RouteSpec {
 
 impl Ord for RouteSpec {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.segments
-            .iter()
-            .zip(&other.segments)
-            .map(|(mine, theirs)| mine.cmp(theirs))
-            .chain(std::iter::once_with(|| {
-                other.segments.len().cmp(&self.segments.len())
-            }))
-            .find(|c| *c != Ordering::Equal)
-            .unwrap_or(Ordering::Less)
+        self.precedence_cmp(other, false).then(Ordering::Less)
     }
 }
@@ -1,27 +1,74 @@
-use std::collections::BTreeSet;
-use std::convert::TryInto;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::hash::{BuildHasher, BuildHasherDefault};
+use std::ops::ControlFlow;
 
-use crate::{Match, Matches, Route, RouteSpec};
+use crate::route::{path_segment_count, render_path, MatchOptions, RouteOrder};
+use crate::{
+    Captures, Entry, FromCaptures, FromParams, Handler, Match, MatchResult, Matches, Route,
+    RouteSpec, Segment, TypedRoute,
+};
+
+/// How a [`Router`] chooses between routes that match the same path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precedence {
+    /// the most specific route wins, according to `Exact > Param >
+    /// Wildcard`, and the first route added wins between routes of
+    /// equal precedence. this is the default
+    #[default]
+    Structural,
+    /// the first route added wins, regardless of specificity
+    InsertionOrder,
+}
+
+/// What a [`Router`] reports to the hook set with
+/// [`Router::set_metrics`] after each [`Router::best_match`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchMetrics {
+    /// the length of the path, in bytes
+    pub path_len: usize,
+    /// how many routes were tried or skipped before the best match
+    /// was found, or every route if there was no match. a route made
+    /// up only of exact segments may be found by a direct lookup
+    /// without scanning, in which case this is zero
+    pub routes_scanned: usize,
+    /// whether a route matched
+    pub matched: bool,
+}
 
 /// a router represents an ordered set of routes which can be applied
 /// to a given request path, and any handler T that is associated with
-/// each route
-
-pub struct Router<T> {
-    routes: BTreeSet<Route<T>>,
+/// each route. routes made up only of exact segments are also kept in
+/// a map from their path, hashed with S, so that they can be found
+/// without trying every route. the default hasher is deterministic;
+/// see [`Router::with_hasher`] to choose another
+pub struct Router<T, S = BuildHasherDefault<DefaultHasher>> {
+    /// sorted from lowest to highest precedence
+    routes: Vec<Route<T>>,
+    /// the index of the highest precedence all-exact route for each
+    /// path, without leading or trailing slashes
+    statics: HashMap<String, usize, S>,
+    /// whether any route has a separator other than `/`, in which case
+    /// `statics` is not used
+    mixed_separators: bool,
+    base_path: Option<RouteSpec>,
+    options: MatchOptions,
+    order: RouteOrder,
+    added: usize,
+    metrics: Option<Box<dyn Fn(MatchMetrics) + Send + Sync>>,
 }
 
-impl<T> std::fmt::Debug for Router<T> {
+impl<T, S> std::fmt::Debug for Router<T, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_set().entries(self.routes.iter()).finish()
     }
 }
 
-impl<T> Default for Router<T> {
+impl<T, S: Default> Default for Router<T, S> {
     fn default() -> Self {
-        Self {
-            routes: BTreeSet::new(),
-        }
+        Self::with_hasher(S::default())
     }
 }
 
@@ -37,22 +84,274 @@ impl<T> Router<T> {
         Self::default()
     }
 
+    /// builds a router from routes and handlers, attempting every
+    /// route and returning all of the errors together, in order, if
+    /// any route was an error or failed to parse
+    pub(crate) fn from_routes(
+        routes: impl IntoIterator<Item = Result<(String, T), String>>,
+    ) -> Result<Self, Vec<String>> {
+        let mut router = Self::new();
+        let errors: Vec<String> = routes
+            .into_iter()
+            .filter_map(|route| match route {
+                Ok((route, handler)) => router.add(route, handler).err(),
+                Err(error) => Some(error),
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(router)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl<T, S> Router<T, S> {
+    /// Builds a new router that hashes the paths of its exact routes
+    /// with `hasher`, such as a randomly keyed hasher to resist
+    /// hash flooding from attacker-controlled paths
+    ///
+    /// ```rust
+    /// use std::collections::hash_map::RandomState;
+    /// let mut router = routefinder::Router::with_hasher(RandomState::new());
+    /// router.add("/about", ()).unwrap();
+    /// assert!(router.best_match("/about").is_some());
+    /// ```
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            routes: Vec::new(),
+            statics: HashMap::with_hasher(hasher),
+            mixed_separators: false,
+            base_path: None,
+            options: MatchOptions::default(),
+            order: RouteOrder::default(),
+            added: 0,
+            metrics: None,
+        }
+    }
+}
+
+impl<T, S: BuildHasher> Router<T, S> {
     /// Adds a route to the router, accepting any type that implements TryInto<[`RouteSpec`]>. In most circumstances, this will be a &str or a String.
     ///
     /// ```rust
     /// let mut router = routefinder::Router::new();
-    /// assert!(router.add("*named_wildcard", ()).is_err());
+    /// assert!(router.add("/*/trailing", ()).is_err());
     /// assert!(router.add("*", ()).is_ok());
+    /// assert!(router.add("/files/*path", ()).is_ok());
     /// assert!(router.add(format!("/dynamic/{}", "route"), ()).is_ok());
     /// ```
     pub fn add<R>(&mut self, route: R, handler: T) -> Result<(), <R as TryInto<RouteSpec>>::Error>
     where
         R: TryInto<RouteSpec>,
     {
-        self.routes.insert(Route::new(route, handler)?);
+        self.insert(Route::new(route, handler)?);
+        Ok(())
+    }
+
+    /// Adds a route like [`Router::add`], marking it as deprecated so
+    /// that matches for it report [`Match::is_deprecated`], such as to
+    /// send a `Deprecation` header. Deprecation does not change route
+    /// precedence.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add_deprecated("/v1/users", ()).unwrap();
+    /// router.add("/v2/users", ()).unwrap();
+    /// assert!(router.best_match("/v1/users").unwrap().is_deprecated());
+    /// assert!(!router.best_match("/v2/users").unwrap().is_deprecated());
+    /// ```
+    pub fn add_deprecated<R>(
+        &mut self,
+        route: R,
+        handler: T,
+    ) -> Result<(), <R as TryInto<RouteSpec>>::Error>
+    where
+        R: TryInto<RouteSpec>,
+    {
+        self.insert(Route::new(route, handler)?.deprecate());
+        Ok(())
+    }
+
+    /// Adds a route whose spec and matched paths are separated by
+    /// `separator` instead of `/`, allowing routes for different
+    /// identifier schemes to share one router. See
+    /// [`RouteSpec::parse_with_separator`].
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", "path").unwrap();
+    /// router.add_with_separator("events.:name", "event", '.').unwrap();
+    /// assert_eq!(*router.best_match("/users/1").unwrap().handler(), "path");
+    ///
+    /// let m = router.best_match("events.signup").unwrap();
+    /// assert_eq!(*m.handler(), "event");
+    /// assert_eq!(m.captures().get("name"), Some("signup"));
+    /// ```
+    pub fn add_with_separator(
+        &mut self,
+        route: &str,
+        handler: T,
+        separator: char,
+    ) -> Result<(), String> {
+        let spec = RouteSpec::parse_with_separator(route, separator)?;
+        self.insert(Route::from_spec(spec, handler));
+        Ok(())
+    }
+
+    /// Adds a [`TypedRoute`], which only matches paths whose params
+    /// parse as its types, as with [`TypedRoute::is_match`]. This
+    /// applies to every way of matching, such as [`Router::best_match`]
+    /// and [`Router::matches`], and the params can be retrieved as
+    /// typed values with [`Router::best_match_typed`].
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add_typed(routefinder::typed_route!("/users/:id" as (u64)).unwrap(), "user");
+    /// assert!(router.best_match("/users/5").is_some());
+    /// assert!(router.best_match("/users/jane").is_none());
+    /// ```
+    pub fn add_typed<P: FromParams>(&mut self, route: TypedRoute<P>, handler: T) {
+        let route = Route::from_spec(route.into_spec(), handler);
+        self.insert(route.with_param_check(P::rejected_param));
+    }
+
+    /// Removes every route, keeping the router's settings and its
+    /// allocations, and makes room for at least `capacity` routes, so
+    /// that the router can be rebuilt without reallocating, such as
+    /// when reloading configuration
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/old", ()).unwrap();
+    /// router.clear_and_reserve(1);
+    /// router.add("/new", ()).unwrap();
+    /// assert!(router.best_match("/old").is_none());
+    /// assert!(router.best_match("/new").is_some());
+    /// ```
+    pub fn clear_and_reserve(&mut self, capacity: usize) {
+        self.routes.clear();
+        self.routes.reserve(capacity);
+        self.statics.clear();
+        self.mixed_separators = false;
+        self.added = 0;
+    }
+
+    /// Gets the [`Entry`] for a route spec, which can be used to
+    /// insert a handler if the route is not yet in the router or
+    /// modify the existing handler if it is, similar to
+    /// [`HashMap::entry`](std::collections::HashMap::entry). Routes
+    /// are considered the same if their [`RouteSpec`]s are equal.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.entry("/hits").unwrap().and_modify(|n| *n += 1).or_insert(1);
+    /// router.entry("/hits").unwrap().and_modify(|n| *n += 1).or_insert(1);
+    /// assert_eq!(*router.best_match("/hits").unwrap().handler(), 2);
+    /// ```
+    pub fn entry<R>(
+        &mut self,
+        route: R,
+    ) -> Result<Entry<'_, T, S>, <R as TryInto<RouteSpec>>::Error>
+    where
+        R: TryInto<RouteSpec>,
+    {
+        let spec = route.try_into()?;
+        let index = self.position(&spec);
+        Ok(Entry::new(self, spec, index))
+    }
+
+    /// Swaps the handlers of two routes in place, identified by their
+    /// specs as with [`Router::entry`]. Returns an error, leaving the
+    /// router unchanged, if either spec fails to parse or is not in
+    /// the router.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/a", "a").unwrap();
+    /// router.add("/b", "b").unwrap();
+    /// router.swap_handlers("/a", "/b").unwrap();
+    /// assert_eq!(*router.best_match("/a").unwrap().handler(), "b");
+    /// assert!(router.swap_handlers("/a", "/c").is_err());
+    /// ```
+    pub fn swap_handlers<R>(&mut self, a: R, b: R) -> Result<(), String>
+    where
+        R: TryInto<RouteSpec>,
+        R::Error: Into<String>,
+    {
+        let position = |route: R| {
+            let spec = route.try_into().map_err(Into::into)?;
+            self.position(&spec)
+                .ok_or_else(|| format!("no route found for `{}`", spec))
+        };
+
+        let (a, b) = (position(a)?, position(b)?);
+        if a != b {
+            let (low, high) = (a.min(b), a.max(b));
+            let (left, right) = self.routes.split_at_mut(high);
+            std::mem::swap(left[low].handler_mut(), right[0].handler_mut());
+        }
+
         Ok(())
     }
 
+    /// Moves every route under the `from` prefix to the `to` prefix,
+    /// such as when migrating an api from `/v1` to `/v2`, returning
+    /// how many routes were moved. A route is under the prefix if it
+    /// starts with all of the prefix's segments, so `/v1` moves
+    /// `/v1` and `/v1/users` but not `/v10`. The prefixes cannot
+    /// contain a wildcard. If a moved route would collide with a
+    /// route that isn't moving, this returns an error and the router
+    /// is unchanged.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/v1/users/:id", ()).unwrap();
+    /// assert_eq!(router.remap_prefix("/v1", "/v2").unwrap(), 1);
+    /// assert!(router.best_match("/v2/users/1").is_some());
+    /// assert!(router.best_match("/v1/users/1").is_none());
+    /// ```
+    pub fn remap_prefix(&mut self, from: &str, to: &str) -> Result<usize, String> {
+        let from: RouteSpec = from.parse()?;
+        let to: RouteSpec = to.parse()?;
+        if from.segments().contains(&Segment::Wildcard)
+            || to.segments().contains(&Segment::Wildcard)
+        {
+            return Err(String::from("a prefix cannot contain a wildcard"));
+        }
+
+        let remapped: Vec<(usize, RouteSpec)> = self
+            .routes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, route)| {
+                route
+                    .definition()
+                    .replace_prefix(&from, &to)
+                    .map(|spec| (index, spec))
+            })
+            .collect();
+
+        for (_, spec) in &remapped {
+            let collision = self.routes.iter().enumerate().any(|(index, route)| {
+                route.definition() == spec && !remapped.iter().any(|(i, _)| *i == index)
+            });
+
+            if collision {
+                return Err(format!("`{}` is already in the router", spec));
+            }
+        }
+
+        let count = remapped.len();
+        for (index, spec) in remapped {
+            self.routes[index].set_definition(spec, self.base_path.as_ref());
+        }
+
+        self.sort();
+        Ok(count)
+    }
+
     /// Returns _all_ of the matching routes for a given path. This is
     /// probably not what you want, as [`Router::best_match`] is more
     /// efficient. The primary reason you'd want to use `matches` is
@@ -71,7 +370,46 @@ impl<T> Router<T> {
     /// assert_eq!(router.matches("/hey/there").len(), 1);
     /// ```
     pub fn matches<'a, 'b>(&'a self, path: &'b str) -> Matches<'a, 'b, T> {
-        Matches::for_routes_and_path(self.routes.iter(), path)
+        Matches::for_routes_path_and_options(
+            self.routes.iter(),
+            path,
+            self.base_path.as_ref(),
+            &self.options,
+            self.order,
+        )
+    }
+
+    /// Calls `f` with each matching route for this path, from highest
+    /// to lowest precedence, stopping as soon as `f` returns
+    /// [`ControlFlow::Break`]. Unlike [`Router::matches`], the matches
+    /// are not collected, and routes after a break are never applied
+    /// to the path. Each [`Match`] holds its captures inline, so
+    /// visiting doesn't allocate unless a route has more than eight
+    /// params and wildcards, including any in the base path.
+    ///
+    /// ```rust
+    /// use std::ops::ControlFlow;
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/*", "wildcard").unwrap();
+    /// router.add("/:param", "param").unwrap();
+    /// router.add("/hello", "exact").unwrap();
+    ///
+    /// let mut visited = vec![];
+    /// router.visit_matches("/hello", |m| {
+    ///     visited.push(*m.handler());
+    ///     ControlFlow::Continue(())
+    /// });
+    /// assert_eq!(visited, vec!["exact", "param", "wildcard"]);
+    /// ```
+    pub fn visit_matches<F>(&self, path: &str, mut f: F)
+    where
+        F: FnMut(&Match<'_, '_, T>) -> ControlFlow<()>,
+    {
+        for m in self.matches_by_precedence(path) {
+            if f(&m).is_break() {
+                return;
+            }
+        }
     }
 
     /// Returns the single best route match as defined by the sorting
@@ -81,8 +419,740 @@ impl<T> Router<T> {
     /// As a result, `/hello` > `/:param` > `/*`.  Because we can sort
     /// the routes before encountering a path, we evaluate them from
     /// highest to lowest weight and an early return as soon as we
-    /// find a match.
+    /// find a match. Routes that cannot match the number of segments
+    /// in the path (see [`Route::segment_count`]) are skipped without
+    /// attempting a full match.
+    ///
+    /// This precedence is the supported way to carve exceptions out of
+    /// a catch-all: an exact route always beats a param or wildcard
+    /// route at the same position, regardless of the order the routes
+    /// were added in.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/api/*", "api").unwrap();
+    /// router.add("/api/health", "health").unwrap();
+    /// assert_eq!(*router.best_match("/api/health").unwrap().handler(), "health");
+    /// assert_eq!(*router.best_match("/api/users").unwrap().handler(), "api");
+    /// ```
+    ///
+    /// Precedence only decides between routes that match, and a route
+    /// without a wildcard only matches paths with exactly as many
+    /// segments as the route. So if both `/a/b` and `/a/*` are
+    /// registered, `/a/b` wins for `/a/b`, but `/a/b/c` is left to the
+    /// wildcard, since `/a/b` cannot match it at all.
     pub fn best_match<'a, 'b>(&'a self, path: &'b str) -> Option<Match<'a, 'b, T>> {
-        self.routes.iter().rev().find_map(|r| r.is_match(path))
+        let metrics = match &self.metrics {
+            Some(metrics) => metrics,
+            None => {
+                return match self.static_match(path) {
+                    Some((_, m)) => Some(m),
+                    None => self.matches_by_precedence(path).next(),
+                }
+            }
+        };
+
+        let (best, routes_scanned) = self.best_match_scanned(path);
+        metrics(MatchMetrics {
+            path_len: path.len(),
+            routes_scanned,
+            matched: best.is_some(),
+        });
+
+        best
+    }
+
+    /// Sets a hook that is called with [`MatchMetrics`] after each
+    /// [`Router::best_match`], to observe the cost of routing. There
+    /// is no cost when no hook is set. [`Router::best_match_copied`]
+    /// and [`Router::dispatch_call`] also report metrics, since they
+    /// call `best_match`. [`Router::best_partial`] reports once for the
+    /// whole input, counting the routes scanned for every prefix it
+    /// tried.
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// let misses = Arc::new(AtomicUsize::new(0));
+    /// let counter = misses.clone();
+    /// router.set_metrics(move |metrics| {
+    ///     if !metrics.matched {
+    ///         counter.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// });
+    ///
+    /// router.best_match("/users/1");
+    /// router.best_match("/posts/1");
+    /// assert_eq!(misses.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn set_metrics<F>(&mut self, metrics: F)
+    where
+        F: Fn(MatchMetrics) + Send + Sync + 'static,
+    {
+        self.metrics = Some(Box::new(metrics));
+    }
+
+    /// Removes the hook set with [`Router::set_metrics`], if any
+    pub fn clear_metrics(&mut self) {
+        self.metrics = None;
+    }
+
+    /// Returns the [`Router::best_match`] for the longest prefix of
+    /// the input that any route matches, along with the rest of the
+    /// input, such as the arguments after a command. The input is
+    /// only cut at a space or a slash, and neither is included at the
+    /// start of the remainder. If the whole input matches, the
+    /// remainder is empty. Since params and wildcards can capture
+    /// spaces, a route that ends in one will consume any arguments
+    /// that follow it.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/deploy", "deploy").unwrap();
+    /// let (m, rest) = router.best_partial("/deploy prod --force").unwrap();
+    /// assert_eq!(*m.handler(), "deploy");
+    /// assert_eq!(rest, "prod --force");
+    /// ```
+    pub fn best_partial<'a, 'b>(&'a self, input: &'b str) -> Option<(Match<'a, 'b, T>, &'b str)> {
+        let is_boundary = |c: char| c == ' ' || c == '/';
+        let cuts = std::iter::once(input.len()).chain(
+            input
+                .char_indices()
+                .rev()
+                .filter(|(_, c)| is_boundary(*c))
+                .map(|(index, _)| index),
+        );
+
+        let mut routes_scanned = 0;
+        let best = cuts
+            .filter_map(|index| {
+                let rest = input[index..].trim_start_matches(is_boundary);
+                let (m, scanned) = self.best_match_scanned(&input[..index]);
+                routes_scanned += scanned;
+                m.map(|m| (m.with_remainder(rest), rest))
+            })
+            .next();
+
+        if let Some(metrics) = &self.metrics {
+            metrics(MatchMetrics {
+                path_len: input.len(),
+                routes_scanned,
+                matched: best.is_some(),
+            });
+        }
+
+        best
+    }
+
+    /// [`Router::best_match`] without reporting metrics, along with
+    /// how many routes were tried or skipped before the best match
+    fn best_match_scanned<'a, 'b>(&'a self, path: &'b str) -> (Option<Match<'a, 'b, T>>, usize) {
+        if let Some((_, m)) = self.static_match(path) {
+            return (Some(m), 0);
+        }
+
+        let base = self.base_path.as_ref();
+        let counts = SegmentCounts::new(path, base);
+        let mut routes_scanned = 0;
+        let best = self.routes.iter().rev().find_map(|r| {
+            let m = Some(r)
+                .filter(|r| counts.accepts(r))
+                .and_then(|r| r.is_match_with(path, base, &self.options, self.order));
+            if m.is_none() {
+                routes_scanned += 1;
+            }
+            m
+        });
+        (best, routes_scanned)
+    }
+
+    /// Returns the [`Router::best_match`] for this path, or when no
+    /// route matches, whether the path has the shape of a route whose
+    /// param was rejected. Params are only rejected for routes added
+    /// with [`Router::add_typed`], when they don't parse as their
+    /// types, so this lets a "not found" be told apart from a known
+    /// path with a bad param. Unlike `best_match`, this does not
+    /// report [`MatchMetrics`].
+    ///
+    /// ```rust
+    /// use routefinder::{typed_route, MatchResult, Router};
+    /// let mut router = Router::new();
+    /// router.add_typed(typed_route!("/users/:id" as (u64)).unwrap(), ());
+    /// assert!(matches!(router.match_result("/users/1"), MatchResult::Matched(_)));
+    /// match router.match_result("/users/abc") {
+    ///     MatchResult::PathKnownButRejected(reason) => {
+    ///         assert_eq!(reason.param, "id");
+    ///         assert_eq!(reason.value, "abc");
+    ///         assert_eq!(reason.constraint, "u64");
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// assert!(matches!(router.match_result("/posts/1"), MatchResult::NotFound));
+    /// ```
+    pub fn match_result<'a, 'b>(&'a self, path: &'b str) -> MatchResult<'a, 'b, T> {
+        if let Some((_, m)) = self.static_match(path) {
+            return MatchResult::Matched(m);
+        }
+
+        let base = self.base_path.as_ref();
+        let counts = SegmentCounts::new(path, base);
+        let mut rejection = None;
+        for route in self.routes.iter().rev().filter(|r| counts.accepts(r)) {
+            match route.try_match_with(path, base, &self.options, self.order) {
+                Ok(m) => return MatchResult::Matched(m),
+                Err(reason) => rejection = rejection.or(reason),
+            }
+        }
+
+        rejection.map_or(MatchResult::NotFound, MatchResult::PathKnownButRejected)
+    }
+
+    /// Returns the [`Router::best_match`] for each path, in order. This
+    /// is equivalent to calling `best_match` for each path, except
+    /// that the router's settings are only checked once for the whole
+    /// batch.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// let matches = router.best_match_batch(&["/users/1", "/posts/1"]);
+    /// assert!(matches[0].is_some());
+    /// assert!(matches[1].is_none());
+    /// ```
+    pub fn best_match_batch<'a, 'p>(&'a self, paths: &[&'p str]) -> Vec<Option<Match<'a, 'p, T>>> {
+        if self.metrics.is_some() {
+            return paths.iter().map(|path| self.best_match(path)).collect();
+        }
+
+        let uses_statics = self.uses_statics();
+        paths
+            .iter()
+            .map(|path| {
+                uses_statics
+                    .then(|| self.lookup_static(path))
+                    .flatten()
+                    .map(|(_, m)| m)
+                    .or_else(|| self.matches_by_precedence(path).next())
+            })
+            .collect()
+    }
+
+    /// Returns a copy of the handler for the [`Router::best_match`],
+    /// which avoids holding a borrow of the [`Match`] for routers of
+    /// small copyable handlers, such as ids or enums
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", 1u32).unwrap();
+    /// assert_eq!(router.best_match_copied("/users/5"), Some(1));
+    /// assert_eq!(router.best_match_copied("/"), None);
+    /// ```
+    pub fn best_match_copied(&self, path: &str) -> Option<T>
+    where
+        T: Copy,
+    {
+        self.best_match(path).map(|m| *m.handler())
+    }
+
+    /// Returns the highest precedence match whose params, in order,
+    /// parse as the tuple of types `P`, along with the parsed params,
+    /// as with [`TypedRoute::is_match`]. A route with a different
+    /// number of params than `P`, or whose params fail to parse, is
+    /// skipped in favor of the next matching route. Routes added with
+    /// [`Router::add_typed`] also only match if their params parse as
+    /// their own types.
+    ///
+    /// ```rust
+    /// use routefinder::{typed_route, Router};
+    /// let mut router = Router::new();
+    /// router.add_typed(typed_route!("/users/:id" as (u64)).unwrap(), "by id");
+    /// router.add_typed(typed_route!("/users/:name" as (String)).unwrap(), "by name");
+    ///
+    /// let (m, (id,)) = router.best_match_typed::<(u64,)>("/users/5").unwrap();
+    /// assert_eq!((*m.handler(), id), ("by id", 5));
+    /// assert!(router.best_match_typed::<(u64,)>("/users/jane").is_none());
+    ///
+    /// let (m, (name,)) = router.best_match_typed::<(String,)>("/users/jane").unwrap();
+    /// assert_eq!((*m.handler(), name.as_str()), ("by name", "jane"));
+    /// ```
+    pub fn best_match_typed<'a, 'b, P: FromParams>(
+        &'a self,
+        path: &'b str,
+    ) -> Option<(Match<'a, 'b, T>, P)> {
+        self.matches_by_precedence(path)
+            .find_map(|m| m.params().map(|params| (m, params)))
+    }
+
+    /// Returns true if any route matches this path. This stops at
+    /// the first matching route and does not build a [`Match`] or
+    /// collect any captures, except to check the params of routes
+    /// added with [`Router::add_typed`], so it is the cheapest
+    /// possible query.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// assert!(router.is_match("/users/1"));
+    /// assert!(!router.is_match("/users"));
+    /// assert!(!router.is_match("/users/1/posts"));
+    /// ```
+    pub fn is_match(&self, path: &str) -> bool {
+        if self.static_match(path).is_some() {
+            return true;
+        }
+
+        let base = self.base_path.as_ref();
+        let counts = SegmentCounts::new(path, base);
+        self.routes
+            .iter()
+            .rev()
+            .filter(|r| counts.accepts(r))
+            .any(|r| r.matches_path(path, base, &self.options))
+    }
+
+    /// Finds the [`Router::best_match`] for this path, extracts its
+    /// captures into `out` with [`FromCaptures::from_match_into`], and
+    /// returns its handler. Reusing the same `out` for each path
+    /// allows a [`FromCaptures`] type to reuse its allocations, and
+    /// [`Captures`] does so for its params and wildcard. Returns None
+    /// if no route matches or the captures can't be extracted, in
+    /// which case `out` may have been partially updated.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", "user").unwrap();
+    /// let mut captures = routefinder::Captures::default();
+    /// assert_eq!(router.best_match_into("/users/1", &mut captures), Some(&"user"));
+    /// assert_eq!(captures.get("id"), Some("1"));
+    /// ```
+    pub fn best_match_into<P: FromCaptures>(&self, path: &str, out: &mut P) -> Option<&T> {
+        let m = self.best_match(path)?;
+        P::from_match_into(&m, out).ok()?;
+        Some(m.handler())
+    }
+
+    /// Returns true if both paths have a [`Router::best_match`] and it
+    /// is the same route for each, such as when checking whether a
+    /// redirect would lead back to the route that issued it
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// router.add("/admin", ()).unwrap();
+    /// assert!(router.same_route("/users/5", "/users/6"));
+    /// assert!(!router.same_route("/users/5", "/admin"));
+    /// ```
+    pub fn same_route(&self, path_a: &str, path_b: &str) -> bool {
+        match (self.best_match(path_a), self.best_match(path_b)) {
+            (Some(a), Some(b)) => std::ptr::eq(a.route(), b.route()),
+            _ => false,
+        }
+    }
+
+    /// Finds the [`Router::best_match`] for this path and calls its
+    /// [`Handler`] with the match's [`Captures`], returning the
+    /// handler's output, or None if no route matches.
+    ///
+    /// ```rust
+    /// use routefinder::{Captures, Router};
+    /// let mut router: Router<fn(&Captures) -> String> = Router::new();
+    /// router.add("/hello/:name", |c| format!("hello, {}", c.get("name").unwrap())).unwrap();
+    /// assert_eq!(router.dispatch_call("/hello/world").unwrap(), "hello, world");
+    /// assert!(router.dispatch_call("/goodbye").is_none());
+    /// ```
+    pub fn dispatch_call(&self, path: &str) -> Option<T::Output>
+    where
+        T: Handler,
+    {
+        self.best_match(path)
+            .map(|m| m.handler().call(&m.captures()))
+    }
+
+    /// Returns the routes that could be rendered into a path using
+    /// only params with these names, from highest to lowest
+    /// precedence. A route is included if every one of its params,
+    /// and any in the base path, is among the names, so routes without
+    /// params are always included.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id", ()).unwrap();
+    /// router.add("/users/:id/posts/:pid", ()).unwrap();
+    /// let routes = router.routes_accepting(&["id"]);
+    /// assert_eq!(routes.len(), 1);
+    /// assert_eq!(routes[0].definition().to_string(), "/users/:id");
+    /// ```
+    pub fn routes_accepting(&self, param_names: &[&str]) -> Vec<&Route<T>> {
+        let accepted = |segment: &Segment| match segment {
+            Segment::Param(name) => param_names.contains(&&**name),
+            _ => true,
+        };
+
+        let base = self.base_path.as_ref().map_or(&[][..], |b| b.segments());
+        if !base.iter().all(accepted) {
+            return vec![];
+        }
+
+        self.routes
+            .iter()
+            .rev()
+            .filter(|r| r.segments().iter().all(accepted))
+            .collect()
+    }
+
+    /// Returns the params and wildcard that every matching route
+    /// would capture for this path, from highest to lowest
+    /// precedence. This is intended for debugging, such as
+    /// discovering why a param is missing under one route but not
+    /// another.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/:greeting", ()).unwrap();
+    /// router.add("/*", ()).unwrap();
+    /// let all_captures = router.all_captures("/hello");
+    /// assert_eq!(all_captures.len(), 2);
+    /// assert_eq!(all_captures[0].0.to_string(), "/:greeting");
+    /// assert_eq!(all_captures[0].1.get("greeting"), Some("hello"));
+    /// assert_eq!(all_captures[1].0.to_string(), "/*");
+    /// assert_eq!(all_captures[1].1.wildcard(), Some("hello"));
+    /// ```
+    pub fn all_captures(&self, path: &str) -> Vec<(RouteSpec, Captures)> {
+        self.matches_by_precedence(path)
+            .map(|m| (m.route().definition().clone(), m.captures()))
+            .collect()
+    }
+
+    /// the index of the route with this spec, if there is one
+    pub(crate) fn position(&self, spec: &RouteSpec) -> Option<usize> {
+        self.routes.iter().position(|r| r.definition() == spec)
+    }
+
+    /// inserts a route in precedence order, returning its index. a
+    /// route is inserted below any routes of equal precedence, so the
+    /// first of those to be added continues to win
+    pub(crate) fn insert(&mut self, mut route: Route<T>) -> usize {
+        route.set_order(self.added);
+        route.set_base(self.base_path.as_ref());
+        self.added += 1;
+
+        let order = self.order;
+        let index = self
+            .routes
+            .partition_point(|existing| order.compare(&route, existing) == Ordering::Greater);
+        self.routes.insert(index, route);
+
+        for static_index in self.statics.values_mut() {
+            if *static_index >= index {
+                *static_index += 1;
+            }
+        }
+        self.index_static(index);
+        index
+    }
+
+    /// adds the route at this index to `statics` if it is made up of
+    /// exact segments and outranks any route already there
+    fn index_static(&mut self, index: usize) {
+        let definition = self.routes[index].definition();
+        if definition.separator() != '/' {
+            self.mixed_separators = true;
+            return;
+        }
+
+        if let Some(path) = render_path(definition.segments(), '/', |_| None) {
+            let static_index = self
+                .statics
+                .entry(String::from(&path[1..]))
+                .or_insert(index);
+            *static_index = (*static_index).max(index);
+        }
+    }
+
+    /// finds the best match for this path among the routes made up of
+    /// exact segments, along with its index. this is skipped when any
+    /// setting could allow a route that isn't in `statics` to outrank
+    /// one that is
+    fn static_match<'a, 'b>(&'a self, path: &'b str) -> Option<(usize, Match<'a, 'b, T>)> {
+        if !self.uses_statics() {
+            return None;
+        }
+
+        self.lookup_static(path)
+    }
+
+    /// whether [`Router::static_match`] can be used with the current
+    /// routes and settings
+    fn uses_statics(&self) -> bool {
+        !self.statics.is_empty()
+            && !self.mixed_separators
+            && self.base_path.is_none()
+            && !self.options.case_insensitive
+            && self.order.precedence == Precedence::Structural
+    }
+
+    /// [`Router::static_match`], without checking whether it can be
+    /// used
+    fn lookup_static<'a, 'b>(&'a self, path: &'b str) -> Option<(usize, Match<'a, 'b, T>)> {
+        let key = path.trim_start_matches('/').trim_end_matches('/');
+        let index = *self.statics.get(key)?;
+        let m = self.routes[index].is_match_with(path, None, &self.options, self.order)?;
+        Some((index, m))
+    }
+
+    pub(crate) fn route_mut(&mut self, index: usize) -> &mut Route<T> {
+        &mut self.routes[index]
+    }
+
+    /// lazily applies each route to the path, from highest to lowest
+    /// precedence
+    fn matches_by_precedence<'a, 'b>(
+        &'a self,
+        path: &'b str,
+    ) -> impl Iterator<Item = Match<'a, 'b, T>> {
+        let counts = SegmentCounts::new(path, self.base_path.as_ref());
+        self.routes
+            .iter()
+            .rev()
+            .filter(move |r| counts.accepts(r))
+            .filter_map(move |r| {
+                r.is_match_with(path, self.base_path.as_ref(), &self.options, self.order)
+            })
+    }
+
+    /// Sets a base path that must prefix every path before any route
+    /// is applied to the remainder. The base path may contain params,
+    /// which are included in the [`Captures`] for every match
+    /// alongside the route's own params. The base path cannot contain
+    /// a wildcard.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.set_base_path("/:tenant/api").unwrap();
+    /// router.add("/users/:id", ()).unwrap();
+    /// assert!(router.best_match("/users/1").is_none());
+    ///
+    /// let captures = router.best_match("/acme/api/users/1").unwrap().captures();
+    /// assert_eq!(captures.get("tenant"), Some("acme"));
+    /// assert_eq!(captures.get("id"), Some("1"));
+    /// ```
+    pub fn set_base_path<R>(&mut self, base_path: R) -> Result<(), String>
+    where
+        R: TryInto<RouteSpec>,
+        R::Error: Into<String>,
+    {
+        let base_path = base_path.try_into().map_err(Into::into)?;
+        if base_path.segments().contains(&Segment::Wildcard) {
+            return Err(String::from("a base path cannot contain a wildcard"));
+        }
+
+        self.base_path = if base_path.segments().is_empty() {
+            None
+        } else {
+            Some(base_path)
+        };
+
+        for route in &mut self.routes {
+            route.set_base(self.base_path.as_ref());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the base path set with [`Router::set_base_path`], if any
+    pub fn base_path(&self) -> Option<&RouteSpec> {
+        self.base_path.as_ref()
+    }
+
+    /// Sets how [`Router::best_match`] chooses between routes that
+    /// match the same path. See [`Precedence`]. This does not change
+    /// the order of [`Router::matches`], which is always structural.
+    ///
+    /// ```rust
+    /// use routefinder::{Precedence, Router};
+    /// let mut router = Router::new();
+    /// router.add("/*", "wildcard").unwrap();
+    /// router.add("/hello", "exact").unwrap();
+    /// assert_eq!(*router.best_match("/hello").unwrap().handler(), "exact");
+    ///
+    /// router.set_precedence(Precedence::InsertionOrder);
+    /// assert_eq!(*router.best_match("/hello").unwrap().handler(), "wildcard");
+    /// ```
+    pub fn set_precedence(&mut self, precedence: Precedence) {
+        self.order.precedence = precedence;
+        self.sort();
+    }
+
+    /// Returns the [`Precedence`] set with [`Router::set_precedence`]
+    pub fn precedence(&self) -> Precedence {
+        self.order.precedence
+    }
+
+    /// By default, a param outranks a wildcard at the same position,
+    /// so `/:name` beats `/*`. Setting this to true reverses that, so
+    /// that a catch-all can act as a default handler ahead of a
+    /// param. Exact segments still outrank both. As with
+    /// [`Router::set_precedence`], this changes which route
+    /// [`Router::best_match`] chooses, but not the order of
+    /// [`Router::matches`].
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/:name", "param").unwrap();
+    /// router.add("/*", "wildcard").unwrap();
+    /// assert_eq!(*router.best_match("/hello").unwrap().handler(), "param");
+    ///
+    /// router.set_wildcard_outranks_param(true);
+    /// assert_eq!(*router.best_match("/hello").unwrap().handler(), "wildcard");
+    /// ```
+    pub fn set_wildcard_outranks_param(&mut self, wildcard_outranks_param: bool) {
+        self.order.wildcard_outranks_param = wildcard_outranks_param;
+        self.sort();
+    }
+
+    /// re-sorts the routes after the [`RouteOrder`] changes, keeping
+    /// routes of equal precedence in the order they were added
+    fn sort(&mut self) {
+        let order = self.order;
+        self.routes.sort_by(|a, b| order.compare(a, b));
+
+        self.statics.clear();
+        self.mixed_separators = false;
+        for index in 0..self.routes.len() {
+            self.index_static(index);
+        }
+    }
+
+    /// By default, a param must capture at least one character, so
+    /// `/users/:id/posts` does not match `/users//posts`. Setting
+    /// this to true allows params to capture an empty string instead.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:id/posts", ()).unwrap();
+    /// assert!(router.best_match("/users//posts").is_none());
+    ///
+    /// router.set_allow_empty_params(true);
+    /// let m = router.best_match("/users//posts").unwrap();
+    /// assert_eq!(m.captures().get("id"), Some(""));
+    /// ```
+    pub fn set_allow_empty_params(&mut self, allow_empty_params: bool) {
+        self.options.allow_empty_params = allow_empty_params;
+    }
+
+    /// By default, exact segments are case sensitive. Setting this to
+    /// true compares exact segments without regard to ascii case.
+    /// Captured params and wildcards are never changed.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/users/:name", ()).unwrap();
+    /// assert!(router.best_match("/USERS/Jane").is_none());
+    ///
+    /// router.set_case_insensitive(true);
+    /// let m = router.best_match("/USERS/Jane").unwrap();
+    /// assert_eq!(m.captures().get("name"), Some("Jane"));
+    /// ```
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.options.case_insensitive = case_insensitive;
+    }
+
+    /// Routes without a wildcard always require a path with exactly
+    /// as many segments as the route, so `/users/:id` never matches
+    /// `/users/5/extra`. By default, a wildcard matches any number of
+    /// segments, including none. Setting this to true caps each
+    /// wildcard at exactly one non-empty segment, so that wildcard
+    /// routes also require an exact segment count.
+    ///
+    /// ```rust
+    /// let mut router = routefinder::Router::new();
+    /// router.add("/files/*", ()).unwrap();
+    /// assert!(router.best_match("/files/a/b").is_some());
+    ///
+    /// router.set_strict_segment_count(true);
+    /// assert!(router.best_match("/files/a").is_some());
+    /// assert!(router.best_match("/files/a/b").is_none());
+    /// assert!(router.best_match("/files").is_none());
+    /// ```
+    pub fn set_strict_segment_count(&mut self, strict_segment_count: bool) {
+        self.options.strict_segment_count = strict_segment_count;
+    }
+}
+
+impl<S: BuildHasher> Router<usize, S> {
+    /// Returns the handler id of the [`Router::best_match`], for
+    /// routers that only map paths to indexes into a table of
+    /// handlers stored elsewhere
+    ///
+    /// ```rust
+    /// let handlers = ["home", "user"];
+    /// let mut router = routefinder::Router::<usize>::new();
+    /// router.add("/", 0).unwrap();
+    /// router.add("/users/:id", 1).unwrap();
+    ///
+    /// let index = router.best_match_index("/users/5").unwrap();
+    /// assert_eq!(handlers[index], "user");
+    /// assert_eq!(router.best_match_index("/missing"), None);
+    /// ```
+    pub fn best_match_index(&self, path: &str) -> Option<usize> {
+        self.best_match_copied(path)
+    }
+}
+
+/// counts the segments of a path after the base path, to skip routes
+/// that cannot match it without attempting a full match
+#[derive(Clone, Copy)]
+struct SegmentCounts<'a> {
+    path: &'a str,
+    base_count: usize,
+    slash_count: Option<usize>,
+}
+
+impl<'a> SegmentCounts<'a> {
+    fn new(path: &'a str, base: Option<&RouteSpec>) -> Self {
+        let base_count = base.map_or(0, |b| b.segment_count());
+        Self {
+            path,
+            base_count,
+            slash_count: path_segment_count(path, '/').checked_sub(base_count),
+        }
+    }
+
+    /// whether this route could match the path. routes with a
+    /// separator other than `/` count the path's segments separately
+    fn accepts<T>(&self, route: &Route<T>) -> bool {
+        let count = match route.definition().separator() {
+            '/' => self.slash_count,
+            separator => path_segment_count(self.path, separator).checked_sub(self.base_count),
+        };
+
+        count.is_some_and(|count| route.accepts_segment_count(count))
+    }
+}
+
+impl<T> TryFrom<HashMap<String, T>> for Router<T> {
+    type Error = Vec<String>;
+
+    /// Builds a router from a map of route specs to handlers. The
+    /// routes are added in the sorted order of their specs, so the
+    /// result doesn't depend on the map's iteration order. If any spec
+    /// fails to parse, all of the errors are returned together.
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use std::convert::TryFrom;
+    /// let mut routes = HashMap::new();
+    /// routes.insert(String::from("/about"), "about");
+    /// routes.insert(String::from("/contact"), "contact");
+    /// let router = routefinder::Router::try_from(routes).unwrap();
+    /// assert_eq!(*router.best_match("/about").unwrap().handler(), "about");
+    /// ```
+    fn try_from(routes: HashMap<String, T>) -> Result<Self, Self::Error> {
+        let mut routes: Vec<_> = routes.into_iter().collect();
+        routes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Self::from_routes(routes.into_iter().map(Ok))
     }
 }
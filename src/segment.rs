@@ -1,8 +1,12 @@
+use std::borrow::Cow;
+
 /// the internal representation of a parsed component of a route
 ///
 /// as an example, `/hello/:planet/*` would be represented as the
 /// following sequence `[Exact("hello"), Slash, Param("planet"),
-/// Slash, Wildcard]`
+/// Slash, Wildcard]`. the text of exact segments and params is
+/// borrowed when the spec was parsed with
+/// [`RouteSpec::from_static`](crate::RouteSpec::from_static)
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Segment {
     /// represented by a / in the route spec and matching one /
@@ -10,18 +14,73 @@ pub enum Segment {
     /// represented by a . in the route spec and matching one . in the path
     Dot,
     /// represented by any free text in the route spec, this matches
-    /// exactly that text
-    Exact(String),
+    /// exactly that text. a literal `:` or `*` at the start of a
+    /// section can be escaped with a backslash, as in `\:` or `\*`,
+    /// and a literal backslash is written `\\`
+    Exact(Cow<'static, str>),
     /// represented by :name, where name is how the capture will be
     /// available in [`Captures`]. Param captures up to the next slash
-    /// or dot, whichever is next in the spec.
-    Param(String),
+    /// or dot, whichever is next in the spec. At the end of a spec,
+    /// a param captures exactly one segment, so unlike a wildcard,
+    /// `/files/:name` matches `/files/a` but not `/files/a/b`.
+    Param(Cow<'static, str>),
     /// represented by * in the spec, this will capture everything up
     /// to the end of the path. a wildcard will also match nothing
     /// (similar to the regex `(.*)$`). There can only be one wildcard
-    /// per route spec
+    /// per route spec, and it must be the last segment. its name, if
+    /// it has one, is kept on the [`RouteSpec`](crate::RouteSpec)
     Wildcard,
 }
+
+impl Segment {
+    /// compares the precedence of two segments at the same position,
+    /// as with [`Ord`], except that a wildcard beats a param if
+    /// `wildcard_outranks_param` is true
+    pub(crate) fn precedence_cmp(
+        &self,
+        other: &Self,
+        wildcard_outranks_param: bool,
+    ) -> std::cmp::Ordering {
+        match (self, other) {
+            (Segment::Param(_), Segment::Wildcard) | (Segment::Wildcard, Segment::Param(_))
+                if wildcard_outranks_param =>
+            {
+                other.cmp(self)
+            }
+            _ => self.cmp(other),
+        }
+    }
+
+    /// the name of this kind of segment, without any contents
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Segment::Slash => "Slash",
+            Segment::Dot => "Dot",
+            Segment::Exact(_) => "Exact",
+            Segment::Param(_) => "Param",
+            Segment::Wildcard => "Wildcard",
+        }
+    }
+}
+
+/// a single positional difference between two
+/// [`RouteSpec`](crate::RouteSpec)s, as returned by
+/// [`RouteSpec::diff`](crate::RouteSpec::diff). indexes refer to
+/// positions in the [`Segment`] sequence, including slashes and dots
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SegmentDiff {
+    /// the segment at `index` differs in kind, literal, or param name
+    Changed {
+        index: usize,
+        from: Segment,
+        to: Segment,
+    },
+    /// the other spec has an additional segment at `index`
+    Added { index: usize, segment: Segment },
+    /// the other spec ends before `index`
+    Removed { index: usize, segment: Segment },
+}
+
 impl PartialOrd for Segment {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
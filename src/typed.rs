@@ -0,0 +1,190 @@
+use std::convert::TryInto;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::route::MatchOptions;
+use crate::{RouteSpec, Segment};
+
+/// A tuple of param types that a [`TypedRoute`] parses its params
+/// into, in the order they appear in the route. This is implemented
+/// for tuples of up to six [`FromStr`] types.
+pub trait FromParams: Sized {
+    /// the number of params in the tuple
+    const ARITY: usize;
+
+    /// parses each param as the corresponding type in the tuple,
+    /// returning None if any fails to parse
+    fn from_params(params: &[&str]) -> Option<Self>;
+
+    /// the index of the first param that is missing or fails to parse
+    /// as its type, along with the name of that type, or None if
+    /// [`FromParams::from_params`] would succeed. by default, this
+    /// reports the first param and the name of the whole tuple
+    fn rejected_param(params: &[&str]) -> Option<(usize, &'static str)> {
+        match Self::from_params(params) {
+            Some(_) => None,
+            None => Some((0, std::any::type_name::<Self>())),
+        }
+    }
+}
+
+macro_rules! impl_from_params {
+    ($arity:expr; $($ty:ident $index:tt),*) => {
+        impl<$($ty: FromStr),*> FromParams for ($($ty,)*) {
+            const ARITY: usize = $arity;
+
+            #[allow(unused_variables)]
+            fn from_params(params: &[&str]) -> Option<Self> {
+                Some(($(params.get($index)?.parse::<$ty>().ok()?,)*))
+            }
+
+            #[allow(unused_variables)]
+            fn rejected_param(params: &[&str]) -> Option<(usize, &'static str)> {
+                $(
+                    if params.get($index).map_or(true, |p| p.parse::<$ty>().is_err()) {
+                        return Some(($index, std::any::type_name::<$ty>()));
+                    }
+                )*
+                None
+            }
+        }
+    };
+}
+
+impl_from_params!(0;);
+impl_from_params!(1; A 0);
+impl_from_params!(2; A 0, B 1);
+impl_from_params!(3; A 0, B 1, C 2);
+impl_from_params!(4; A 0, B 1, C 2, D 3);
+impl_from_params!(5; A 0, B 1, C 2, D 3, E 4);
+impl_from_params!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// the number of params in a route spec, counted at compile time by
+/// [`typed_route!`](crate::typed_route). this counts each section
+/// that starts with a `:`, so it agrees with the parser for any spec
+/// that parses
+#[doc(hidden)]
+pub const fn param_count(route: &str) -> usize {
+    let bytes = route.as_bytes();
+    let mut count = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b':' && (index == 0 || bytes[index - 1] == b'/') {
+            count += 1;
+        }
+        index += 1;
+    }
+    count
+}
+
+/// A [`RouteSpec`] that parses its params into a tuple of types `P`,
+/// so that a match yields typed values without looking params up by
+/// name. A path only matches if every param parses as its type. Build
+/// one with [`typed_route!`](crate::typed_route), or with
+/// [`TypedRoute::new`], and add it to a router with
+/// [`Router::add_typed`](crate::Router::add_typed).
+///
+/// ```rust
+/// let route = routefinder::typed_route!("/users/:id/posts/:slug" as (u64, String)).unwrap();
+/// assert_eq!(
+///     route.is_match("/users/5/posts/hello"),
+///     Some((5, String::from("hello")))
+/// );
+/// assert_eq!(route.is_match("/users/five/posts/hello"), None);
+/// ```
+pub struct TypedRoute<P> {
+    spec: RouteSpec,
+    params: PhantomData<fn() -> P>,
+}
+
+impl<P> std::fmt::Debug for TypedRoute<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("TypedRoute({:?})", &self.spec))
+    }
+}
+
+impl<P: FromParams> TypedRoute<P> {
+    /// parses the route, returning an error if it fails to parse or if
+    /// its number of params differs from the number of types in `P`.
+    /// a wildcard is allowed, but is not parsed into `P`
+    pub fn new<R>(route: R) -> Result<Self, String>
+    where
+        R: TryInto<RouteSpec>,
+        R::Error: Into<String>,
+    {
+        let spec = route.try_into().map_err(Into::into)?;
+        let params = spec
+            .segments()
+            .iter()
+            .filter(|s| matches!(s, Segment::Param(_)))
+            .count();
+
+        if params != P::ARITY {
+            return Err(format!(
+                "`{}` has {} params, but its type has {}",
+                spec,
+                params,
+                P::ARITY
+            ));
+        }
+
+        Ok(Self {
+            spec,
+            params: PhantomData,
+        })
+    }
+
+    /// the [`RouteSpec`] for this route
+    pub fn spec(&self) -> &RouteSpec {
+        &self.spec
+    }
+
+    pub(crate) fn into_spec(self) -> RouteSpec {
+        self.spec
+    }
+
+    /// applies this route to the path, returning the parsed params if
+    /// the path matches and every param parses as its type
+    pub fn is_match(&self, path: &str) -> Option<P> {
+        let mut params = Vec::with_capacity(P::ARITY);
+        let mut captures = self
+            .spec
+            .segments()
+            .iter()
+            .filter(|s| matches!(s, Segment::Param(_) | Segment::Wildcard));
+
+        self.spec
+            .apply(path, None, &MatchOptions::default(), |capture| {
+                if let Some(Segment::Param(_)) = captures.next() {
+                    params.push(capture);
+                }
+            })?;
+
+        P::from_params(&params)
+    }
+}
+
+/// Builds a [`TypedRoute`] from a route spec literal and a tuple of
+/// param types, in the order the params appear in the route. A route
+/// with a different number of params than types fails to compile.
+/// This returns an error if the route fails to parse.
+///
+/// ```rust
+/// let route = routefinder::typed_route!("/users/:id" as (u64)).unwrap();
+/// assert_eq!(route.is_match("/users/5"), Some((5,)));
+/// ```
+///
+/// ```rust,compile_fail
+/// let route = routefinder::typed_route!("/users/:id" as (u64, u64));
+/// ```
+#[macro_export]
+macro_rules! typed_route {
+    ($route:literal as ($($ty:ty),* $(,)?)) => {{
+        const _: () = assert!(
+            $crate::__param_count($route)
+                == <($($ty,)*) as $crate::FromParams>::ARITY,
+            "the number of params in the route differs from the number of types"
+        );
+        $crate::TypedRoute::<($($ty,)*)>::new($route)
+    }};
+}
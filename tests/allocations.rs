@@ -0,0 +1,107 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ops::ControlFlow;
+
+use routefinder::*;
+
+/// counts the allocations made by each thread, so that tests running
+/// in parallel don't count each other's allocations
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn record_allocation() {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn matching_does_not_allocate() -> Result<(), String> {
+    let mut router = Router::new();
+    router.set_base_path("/:tenant")?;
+    router.add("/users/:id/posts/:post/*", 1)?;
+    router.add("/users/:id/*", 2)?;
+    router.add("/about", 3)?;
+
+    let mut visited = Vec::with_capacity(2);
+    let allocations = count_allocations(|| {
+        router.visit_matches("/acme/users/1/posts/2/a/b", |m| {
+            visited.push(*m.handler());
+            ControlFlow::Continue(())
+        })
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(visited, vec![1, 2]);
+
+    let allocations = count_allocations(|| {
+        let m = router.best_match("/acme/users/1/posts/2/a/b").unwrap();
+        assert_eq!(m.named_captures().nth(2), Some(("post", "2")));
+    });
+    assert_eq!(allocations, 0);
+
+    let mut statics = Router::new();
+    statics.add("/about/team", ())?;
+    let m = statics.best_match("about/team/").unwrap();
+    let mut exact = false;
+    assert_eq!(count_allocations(|| exact = m.is_exact_match()), 0);
+    assert!(exact);
+
+    router.add("/:a/:b/:c/:d/:e/:f/:g/:h", 4)?;
+    let allocations = count_allocations(|| {
+        router.best_match("/acme/a/b/c/d/e/f/g/h").unwrap();
+    });
+    assert!(allocations > 0);
+
+    Ok(())
+}
+
+#[test]
+fn best_match_into_reuses_captures() -> Result<(), String> {
+    let mut router = Router::new();
+    router.add("/:user/posts/:post", "post")?;
+    router.add("/:user/files/*", "files")?;
+
+    let mut captures = Captures::default();
+    router.best_match_into("/jane/posts/1", &mut captures);
+    let allocations = count_allocations(|| {
+        router.best_match_into("/john/posts/2", &mut captures);
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(captures.get("post"), Some("2"));
+
+    router.best_match_into("/jane/files/a/b", &mut captures);
+    let allocations = count_allocations(|| {
+        router.best_match_into("/john/files/c", &mut captures);
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(captures.get("user"), Some("john"));
+    assert_eq!(captures.wildcard(), Some("c"));
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "serde")]
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+use routefinder::*;
+use std::collections::HashMap;
+
+#[test]
+fn from_config() -> Result {
+    let entries: Vec<RouteConfig> = serde_json::from_str(
+        r#"[
+            { "path": "/users/:id", "handler_key": "users" },
+            { "path": "/users/:id/posts", "handler_key": "posts" },
+            { "path": "/people/:id", "handler_key": "users" }
+        ]"#,
+    )?;
+
+    let mut handlers = HashMap::new();
+    handlers.insert(String::from("users"), 1);
+    handlers.insert(String::from("posts"), 2);
+
+    let router = Router::from_config(entries, &handlers).unwrap();
+    assert_eq!(*router.best_match("/users/1").unwrap().handler(), 1);
+    assert_eq!(*router.best_match("/people/1").unwrap().handler(), 1);
+    assert_eq!(*router.best_match("/users/1/posts").unwrap().handler(), 2);
+    assert!(router.best_match("/").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn from_config_errors() -> Result {
+    let entries: Vec<RouteConfig> = serde_json::from_str(
+        r#"[
+            { "path": "/:", "handler_key": "users" },
+            { "path": "/users/:id", "handler_key": "users" },
+            { "path": "/posts", "handler_key": "missing" }
+        ]"#,
+    )?;
+
+    let mut handlers = HashMap::new();
+    handlers.insert(String::from("users"), ());
+
+    let errors = Router::from_config(entries, &handlers).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            String::from("params must be named"),
+            String::from("no handler found for key `missing` (route `/posts`)"),
+        ]
+    );
+
+    Ok(())
+}
@@ -131,9 +131,1883 @@ fn errors_on_add() {
     let mut router = Router::new();
 
     assert!(router
-        .add("*named_star", ())
+        .add("/*/more", ())
         .unwrap_err()
-        .contains("replace `*named_star` with `*`"));
+        .contains("a wildcard must be the last segment"));
 
     assert_eq!(router.add(":", ()).unwrap_err(), "params must be named");
 }
+
+#[test]
+fn params_must_not_be_empty_by_default() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id/posts", ())?;
+    assert!(router.best_match("/users//posts").is_none());
+    assert!(router.matches("/users//posts").is_empty());
+    let best_match = router.best_match("/users/1/posts").unwrap();
+    assert_eq!(best_match.captures().get("id"), Some("1"));
+
+    Ok(())
+}
+
+#[test]
+fn allow_empty_params() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id/posts", ())?;
+    router.set_allow_empty_params(true);
+
+    let best_match = router.best_match("/users//posts").unwrap();
+    assert_eq!(best_match.captures().get("id"), Some(""));
+    assert_eq!(router.matches("/users//posts").len(), 1);
+
+    let best_match = router.best_match("/users/1/posts").unwrap();
+    assert_eq!(best_match.captures().get("id"), Some("1"));
+
+    Ok(())
+}
+
+#[test]
+fn diff_specs() -> Result {
+    let users: RouteSpec = "/users/:id".parse()?;
+    let people: RouteSpec = "/people/:id".parse()?;
+
+    assert_eq!(
+        users.diff(&people),
+        vec![SegmentDiff::Changed {
+            index: 0,
+            from: Segment::Exact("users".into()),
+            to: Segment::Exact("people".into()),
+        }]
+    );
+
+    assert!(users.diff(&users).is_empty());
+
+    let renamed: RouteSpec = "/users/:user_id".parse()?;
+    assert_eq!(
+        users.diff(&renamed),
+        vec![SegmentDiff::Changed {
+            index: 2,
+            from: Segment::Param("id".into()),
+            to: Segment::Param("user_id".into()),
+        }]
+    );
+
+    let shorter: RouteSpec = "/users".parse()?;
+    assert_eq!(
+        users.diff(&shorter),
+        vec![
+            SegmentDiff::Removed {
+                index: 1,
+                segment: Segment::Slash
+            },
+            SegmentDiff::Removed {
+                index: 2,
+                segment: Segment::Param("id".into())
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn matched_prefix() -> Result {
+    let mut router = Router::new();
+    router.add("/service-a/*", ())?;
+    router.add("/:service/status", ())?;
+
+    for path in &["/service-a/users/1", "/service-a/a"] {
+        let m = router.best_match(path).unwrap();
+        let captures = m.captures();
+        let wildcard = captures.wildcard().unwrap();
+        assert_eq!(m.matched_prefix(), "/service-a");
+        assert_eq!(
+            format!("{}/{}", m.matched_prefix(), wildcard),
+            path.to_string()
+        );
+    }
+
+    for path in &["/service-a", "/service-a/", "/service-a//"] {
+        let m = router.best_match(path).unwrap();
+        assert_eq!(m.matched_prefix(), "/service-a");
+        assert_eq!(m.captures().wildcard(), Some(""));
+    }
+
+    let m = router.best_match("/service-a/users/1/").unwrap();
+    assert_eq!(m.matched_prefix(), "/service-a");
+    assert_eq!(m.captures().wildcard(), Some("users/1"));
+
+    let m = router.best_match("/service-a//users").unwrap();
+    assert_eq!(m.matched_prefix(), "/service-a");
+    assert_eq!(m.captures().wildcard(), Some("/users"));
+
+    let m = router.best_match("/service-b/status/").unwrap();
+    assert_eq!(m.matched_prefix(), "/service-b/status");
+    assert_eq!(m.captures().wildcard(), None);
+
+    Ok(())
+}
+
+#[test]
+fn rank_explanation() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id", ())?;
+    router.add("/users/new", ())?;
+    router.add("/users/:id/*", ())?;
+    router.add("/*", ())?;
+
+    let matches = router.matches("/users/new");
+    let explanation = |a: usize, b: usize| {
+        let a = matches.iter().nth(a).unwrap();
+        let b = matches.iter().nth(b).unwrap();
+        a.rank_explanation(b)
+    };
+
+    // matches are sorted from lowest to highest precedence:
+    // /*, /users/:id/*, /users/:id, /users/new
+    assert_eq!(
+        explanation(3, 0),
+        "/users/new beat /* because segment 0 Exact > Wildcard"
+    );
+    assert_eq!(
+        explanation(0, 3),
+        "/users/new beat /* because segment 0 Exact > Wildcard"
+    );
+    assert_eq!(
+        explanation(3, 2),
+        "/users/new beat /users/:id because segment 1 Exact > Param"
+    );
+    assert_eq!(
+        explanation(1, 2),
+        "/users/:id beat /users/:id/* because it has fewer segments"
+    );
+    assert_eq!(
+        explanation(2, 2),
+        "/users/:id and /users/:id have equal precedence, so the first one added wins"
+    );
+
+    let mut router = Router::new();
+    router.add("/users/:id", ())?;
+    router.add("/users/*", ())?;
+    router.set_wildcard_outranks_param(true);
+    let best = router.best_match("/users/1").unwrap();
+    let matches = router.matches("/users/1");
+    let param = matches.iter().last().unwrap();
+    assert_eq!(
+        best.rank_explanation(param),
+        "/users/* beat /users/:id because segment 1 Wildcard > Param"
+    );
+    assert_eq!(
+        param.rank_explanation(&best),
+        "/users/* beat /users/:id because segment 1 Wildcard > Param"
+    );
+
+    router.set_precedence(Precedence::InsertionOrder);
+    let matches = router.matches("/users/new");
+    let best = router.best_match("/users/new").unwrap();
+    let wildcard = matches.iter().next().unwrap();
+    assert_eq!(
+        best.rank_explanation(wildcard),
+        "/users/:id beat /users/* because it was added first"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn base_path_with_params() -> Result {
+    let mut router = Router::new();
+    router.set_base_path("/:tenant/api")?;
+    router.add("/users/:id", 1)?;
+    router.add("/", 2)?;
+    router.add("/files/*", 3)?;
+
+    let m = router.best_match("/acme/api/users/5").unwrap();
+    assert_eq!(*m.handler(), 1);
+    let captures = m.captures();
+    assert_eq!(captures.get("tenant"), Some("acme"));
+    assert_eq!(captures.get("id"), Some("5"));
+    assert_eq!(
+        captures.iter().map(|(k, _)| &**k).collect::<Vec<_>>(),
+        vec!["tenant", "id"]
+    );
+
+    let m = router.best_match("/acme/api").unwrap();
+    assert_eq!(*m.handler(), 2);
+    assert_eq!(m.captures().get("tenant"), Some("acme"));
+
+    let m = router.best_match("/initech/api/files/a/b").unwrap();
+    assert_eq!(*m.handler(), 3);
+    assert_eq!(m.captures().get("tenant"), Some("initech"));
+    assert_eq!(m.captures().wildcard(), Some("a/b"));
+
+    assert!(router.best_match("/users/5").is_none());
+    assert!(router.best_match("/acme/users/5").is_none());
+    assert_eq!(router.matches("/acme/api/files").len(), 1);
+
+    assert!(router.set_base_path("/api/*").is_err());
+    router.set_base_path("/")?;
+    assert!(router.base_path().is_none());
+    assert_eq!(*router.best_match("/users/5").unwrap().handler(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn segment_count_pruning_does_not_change_best_match() -> Result {
+    let mut router = Router::new();
+    router.add("/", 1)?;
+    router.add("/a", 2)?;
+    router.add("/a/b", 3)?;
+    router.add("/a/:b", 4)?;
+    router.add("/:a/b/c", 5)?;
+    router.add("/a/*", 6)?;
+    router.add("/a/b/c/*", 7)?;
+    router.add("/*", 8)?;
+
+    for path in &[
+        "/",
+        "/a",
+        "/a/",
+        "/a/b",
+        "/a/x",
+        "/x/b/c",
+        "/a/b/c",
+        "/a/b/c/d",
+        "/a/b/c/d/e",
+        "/x",
+        "/x/y",
+    ] {
+        let best = router.best_match(path).map(|m| *m.handler());
+        let unpruned = router
+            .matches(path)
+            .iter()
+            .next_back()
+            .map(|m| *m.handler());
+        assert_eq!(best, unpruned, "path: {}", path);
+    }
+
+    let mut router = Router::new();
+    router.set_base_path("/api")?;
+    router.add("/a/*", ())?;
+    assert!(router.best_match("/").is_none());
+    assert!(router.best_match("/api/a").is_some());
+    assert!(router.best_match("/api/a/b/c").is_some());
+
+    let segment_counts = ["/", "/a", "/a/:b", "/a/*", "/a/b/c/*"]
+        .iter()
+        .map(|spec| {
+            let mut router = Router::new();
+            router.add(*spec, ()).unwrap();
+            router.best_match(spec).unwrap().route().segment_count()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(segment_counts, vec![0, 1, 2, 2, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn all_captures() -> Result {
+    let mut router = Router::new();
+    router.add("/posts/:id", ())?;
+    router.add("/posts/*", ())?;
+    router.add("/other", ())?;
+
+    let all_captures = router.all_captures("/posts/hello");
+    assert_eq!(all_captures.len(), 2);
+
+    let (spec, captures) = &all_captures[0];
+    assert_eq!(spec.to_string(), "/posts/:id");
+    assert_eq!(captures.get("id"), Some("hello"));
+    assert_eq!(captures.wildcard(), None);
+
+    let (spec, captures) = &all_captures[1];
+    assert_eq!(spec.to_string(), "/posts/*");
+    assert_eq!(captures.get("id"), None);
+    assert_eq!(captures.wildcard(), Some("hello"));
+
+    assert!(router.all_captures("/nothing").is_empty());
+    Ok(())
+}
+
+#[test]
+fn dispatch_call() -> Result {
+    struct Echo(&'static str);
+    impl Handler for Echo {
+        type Output = Option<String>;
+        fn call(&self, captures: &Captures) -> Self::Output {
+            captures.get(self.0).map(String::from)
+        }
+    }
+
+    let mut router = Router::new();
+    router.add("/echo/:message", Echo("message"))?;
+    router.add("/other/:id", Echo("message"))?;
+
+    assert_eq!(
+        router.dispatch_call("/echo/hello"),
+        Some(Some(String::from("hello")))
+    );
+    assert_eq!(router.dispatch_call("/other/1"), Some(None));
+    assert_eq!(router.dispatch_call("/"), None);
+
+    type BoxedHandler = Box<dyn Fn(&Captures) -> usize>;
+    let mut router: Router<BoxedHandler> = Router::new();
+    router.add(
+        "/len/*",
+        Box::new(|c: &Captures| c.wildcard().unwrap().len()),
+    )?;
+    assert_eq!(router.dispatch_call("/len/abc"), Some(3));
+
+    Ok(())
+}
+
+#[test]
+fn exact_routes_carve_exceptions_out_of_wildcards() -> Result {
+    let mut wildcard_first = Router::new();
+    wildcard_first.add("/api/*", "api")?;
+    wildcard_first.add("/api/health", "health")?;
+
+    let mut exact_first = Router::new();
+    exact_first.add("/api/health", "health")?;
+    exact_first.add("/api/*", "api")?;
+
+    for router in &[wildcard_first, exact_first] {
+        assert_eq!(
+            *router.best_match("/api/health").unwrap().handler(),
+            "health"
+        );
+        assert_eq!(
+            *router.best_match("/api/health/").unwrap().handler(),
+            "health"
+        );
+        assert_eq!(*router.best_match("/api/healthz").unwrap().handler(), "api");
+        assert_eq!(
+            *router.best_match("/api/health/x").unwrap().handler(),
+            "api"
+        );
+        assert_eq!(*router.best_match("/api/users/1").unwrap().handler(), "api");
+        assert_eq!(*router.best_match("/api").unwrap().handler(), "api");
+        assert_eq!(router.matches("/api/health").len(), 2);
+    }
+
+    let mut router = Router::new();
+    router.add("/api/:resource", "resource")?;
+    router.add("/api/*", "api")?;
+    router.add("/api/health", "health")?;
+    assert_eq!(
+        *router.best_match("/api/health").unwrap().handler(),
+        "health"
+    );
+    assert_eq!(
+        *router.best_match("/api/users").unwrap().handler(),
+        "resource"
+    );
+    assert_eq!(*router.best_match("/api/users/1").unwrap().handler(), "api");
+
+    Ok(())
+}
+
+#[test]
+fn entry() -> Result {
+    let mut router = Router::new();
+    router.add("/existing", 1)?;
+    router.add("/*", 10)?;
+
+    let entry = router.entry("/new")?;
+    assert!(!entry.is_occupied());
+    assert_eq!(*entry.or_insert(2), 2);
+
+    let entry = router.entry("existing/")?;
+    assert!(entry.is_occupied());
+    assert_eq!(entry.spec().source(), "existing/");
+    assert_eq!(entry.spec().to_string(), "/existing");
+    assert_eq!(*entry.or_insert(100), 1);
+
+    *router
+        .entry("/existing")?
+        .and_modify(|h| *h += 1)
+        .or_insert(100) += 1;
+    router.entry("/other")?.and_modify(|h| *h += 1).or_insert(3);
+
+    assert_eq!(*router.best_match("/new").unwrap().handler(), 2);
+    assert_eq!(*router.best_match("/existing").unwrap().handler(), 3);
+    assert_eq!(*router.best_match("/other").unwrap().handler(), 3);
+    assert_eq!(*router.best_match("/anything/else").unwrap().handler(), 10);
+    assert_eq!(router.matches("/existing").len(), 2);
+
+    assert!(router.entry("/:").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn first_added_route_wins_among_equals() -> Result {
+    let mut router = Router::new();
+    router.add("/:a", 1)?;
+    router.add("/:b", 2)?;
+    router.add("/hello", 3)?;
+    router.add("/:c", 4)?;
+
+    assert_eq!(*router.best_match("/hello").unwrap().handler(), 3);
+    assert_eq!(*router.best_match("/world").unwrap().handler(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn case_insensitive() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id", 1)?;
+    router.add("/USERS/admin", 2)?;
+    assert!(router.best_match("/Users/5").is_none());
+    assert_eq!(*router.best_match("/users/admin").unwrap().handler(), 1);
+
+    router.set_case_insensitive(true);
+    assert_eq!(*router.best_match("/Users/5").unwrap().handler(), 1);
+    assert_eq!(*router.best_match("/users/admin").unwrap().handler(), 2);
+    assert_eq!(router.matches("/uSeRs/ADMIN").len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn canonical_path() -> Result {
+    let mut router = Router::new();
+    router.set_case_insensitive(true);
+    router.add("/users/:id", ())?;
+    router.add("/Files/*", ())?;
+
+    let m = router.best_match("/Users/5/").unwrap();
+    assert_eq!(m.canonical_path(), "/users/5");
+
+    let m = router.best_match("//USERS/Jane").unwrap();
+    assert_eq!(m.canonical_path(), "/users/Jane");
+
+    let m = router.best_match("/files/a/B/c/").unwrap();
+    assert_eq!(m.canonical_path(), "/Files/a/B/c");
+
+    let m = router.best_match("/files").unwrap();
+    assert_eq!(m.canonical_path(), "/Files");
+
+    let mut router = Router::new();
+    router.set_case_insensitive(true);
+    router.set_base_path("/:tenant/API")?;
+    router.add("/", ())?;
+    let m = router.best_match("/acme/api/").unwrap();
+    assert_eq!(m.canonical_path(), "/acme/API");
+
+    Ok(())
+}
+
+#[test]
+fn captures_to_path() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id/posts/:post_id", ())?;
+    router.add("/files/*", ())?;
+
+    let captures = router.best_match("/users/5/posts/10/").unwrap().captures();
+    assert_eq!(
+        captures.spec().unwrap().to_string(),
+        "/users/:id/posts/:post_id"
+    );
+    assert_eq!(captures.to_path().unwrap(), "/users/5/posts/10");
+
+    let captures = router.best_match("/files/a/b").unwrap().captures();
+    assert_eq!(captures.to_path().unwrap(), "/files/a/b");
+
+    let captures = router.best_match("/files").unwrap().captures();
+    assert_eq!(captures.to_path().unwrap(), "/files");
+
+    let mut router = Router::new();
+    router.set_base_path("/:tenant")?;
+    router.add("/users/:id", ())?;
+    let captures = router.best_match("/acme/users/1").unwrap().captures();
+    assert_eq!(captures.spec().unwrap().to_string(), "/:tenant/users/:id");
+    assert_eq!(captures.to_path().unwrap(), "/acme/users/1");
+
+    let other = router.best_match("/globex/users/2").unwrap().captures();
+    assert!(std::ptr::eq(
+        captures.spec().unwrap(),
+        other.spec().unwrap()
+    ));
+
+    router.set_base_path("/:tenant/v2")?;
+    let captures = router.best_match("/acme/v2/users/1").unwrap().captures();
+    assert_eq!(
+        captures.spec().unwrap().to_string(),
+        "/:tenant/v2/users/:id"
+    );
+
+    assert_eq!(Captures::default().to_path(), None);
+
+    Ok(())
+}
+
+#[test]
+fn router_chain_first_router_wins() -> Result {
+    let mut first = Router::new();
+    first.add("/users/:id", "first param")?;
+    first.add("/admin/*", "first admin")?;
+
+    let mut second = Router::new();
+    second.add("/users/me", "second exact")?;
+    second.add("/posts/:id", "second posts")?;
+
+    let mut chain = RouterChain::new();
+    assert!(chain.best_match("/users/me").is_none());
+    chain.push(first);
+    chain.push(second);
+    assert_eq!(chain.policy(), ChainPolicy::FirstRouterWins);
+    assert_eq!(chain.routers().len(), 2);
+
+    assert_eq!(
+        *chain.best_match("/users/me").unwrap().handler(),
+        "first param"
+    );
+    assert_eq!(
+        *chain.best_match("/posts/1").unwrap().handler(),
+        "second posts"
+    );
+    assert!(chain.best_match("/other").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn router_chain_global_best() -> Result {
+    let mut first = Router::new();
+    first.add("/users/:id", "first param")?;
+    first.add("/posts/:id", "first posts")?;
+
+    let mut second = Router::new();
+    second.add("/users/me", "second exact")?;
+    second.add("/posts/:post_id", "second posts")?;
+
+    let mut chain = RouterChain::with_policy(ChainPolicy::GlobalBest);
+    chain.push(first);
+    chain.push(second);
+
+    assert_eq!(
+        *chain.best_match("/users/me").unwrap().handler(),
+        "second exact"
+    );
+    assert_eq!(
+        *chain.best_match("/users/1").unwrap().handler(),
+        "first param"
+    );
+    assert_eq!(
+        *chain.best_match("/posts/1").unwrap().handler(),
+        "first posts"
+    );
+
+    let mut first = Router::new();
+    first.add("/files/:name", "first param")?;
+    let mut second = Router::new();
+    second.add("/files/*", "second wildcard")?;
+    second.set_wildcard_outranks_param(true);
+
+    let mut chain = RouterChain::with_policy(ChainPolicy::GlobalBest);
+    chain.push(second);
+    chain.push(first);
+    assert_eq!(
+        *chain.best_match("/files/a").unwrap().handler(),
+        "second wildcard"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn best_match_copied() -> Result {
+    let mut router: Router<u16> = Router::new();
+    router.add("/users/:id", 1)?;
+    router.add("/users/new", 2)?;
+    router.add("*", 404)?;
+
+    let mut handler = router.best_match_copied("/users/5").unwrap();
+    assert_eq!(handler, 1);
+    handler += 1;
+    assert_eq!(handler, 2);
+    assert_eq!(router.best_match_copied("/users/new"), Some(2));
+    assert_eq!(router.best_match_copied("/anything/else"), Some(404));
+
+    Ok(())
+}
+
+#[test]
+fn strict_segment_count() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id", 1)?;
+    router.add("/files/*", 2)?;
+    router.add("/*", 3)?;
+
+    assert_eq!(*router.best_match("/users/5").unwrap().handler(), 1);
+    assert_eq!(*router.best_match("/users/5/extra").unwrap().handler(), 3);
+    assert_eq!(*router.best_match("/files").unwrap().handler(), 2);
+    assert_eq!(*router.best_match("/files/a/b").unwrap().handler(), 2);
+
+    router.set_strict_segment_count(true);
+    assert_eq!(*router.best_match("/users/5").unwrap().handler(), 1);
+    assert!(router.best_match("/users/5/extra").is_none());
+    assert_eq!(*router.best_match("/files/a").unwrap().handler(), 2);
+    assert_eq!(*router.best_match("/files/a/").unwrap().handler(), 2);
+    assert_eq!(
+        router.best_match("/files/a").unwrap().captures().wildcard(),
+        Some("a")
+    );
+    assert!(router.best_match("/files/a/b").is_none());
+    assert!(!router.is_match("/files/a/b/c"));
+    assert_eq!(*router.best_match("/files").unwrap().handler(), 3);
+    assert!(router.best_match("/").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn routes_require_an_exact_segment_count() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id", ())?;
+    let route = router.best_match("/users/5").unwrap().route();
+    assert!(route.is_match("/users/5").is_some());
+    assert!(route.is_match("/users/5/").is_some());
+    assert!(route.is_match("/users/5/extra").is_none());
+    assert!(route.is_match("/users/5/extra/more").is_none());
+    assert!(route.is_match("/users").is_none());
+    assert!(route.is_match("/users/").is_none());
+    assert!(!router.is_match("/users/5/extra"));
+    assert!(!router.is_match("/users"));
+    assert!(router.matches("/users/5/extra").is_empty());
+
+    let mut router = Router::new();
+    router.add("/users/list", ())?;
+    let route = router.best_match("/users/list").unwrap().route();
+    assert!(route.is_match("/users/list/extra").is_none());
+    assert!(route.is_match("/users").is_none());
+    assert!(route.is_match("/users/listing").is_none());
+    assert!(route.is_match("/users/lis").is_none());
+
+    let mut router = Router::new();
+    router.add("/:a/:b/:c", ())?;
+    assert!(router.is_match("/1/2/3"));
+    assert!(!router.is_match("/1/2"));
+    assert!(!router.is_match("/1/2/3/4"));
+
+    Ok(())
+}
+
+#[test]
+fn extra_segments_fall_through_to_wildcards() -> Result {
+    let mut router = Router::new();
+    router.add("/a/b", "exact")?;
+    router.add("/a/*", "wildcard")?;
+
+    assert_eq!(*router.best_match("/a/b").unwrap().handler(), "exact");
+    assert_eq!(router.matches("/a/b").len(), 2);
+
+    let m = router.best_match("/a/b/c").unwrap();
+    assert_eq!(*m.handler(), "wildcard");
+    assert_eq!(m.captures().wildcard(), Some("b/c"));
+    assert_eq!(router.matches("/a/b/c").len(), 1);
+
+    assert_eq!(*router.best_match("/a").unwrap().handler(), "wildcard");
+
+    Ok(())
+}
+
+#[test]
+fn router_from_hash_map() -> Result {
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+
+    let mut routes = HashMap::new();
+    routes.insert(String::from("/"), 1);
+    routes.insert(String::from("/about"), 2);
+    routes.insert(String::from("/about/team"), 3);
+
+    let router = Router::try_from(routes).unwrap();
+    assert_eq!(router.best_match_copied("/"), Some(1));
+    assert_eq!(router.best_match_copied("/about"), Some(2));
+    assert_eq!(router.best_match_copied("/about/team"), Some(3));
+    assert_eq!(router.best_match_copied("/contact"), None);
+
+    let mut routes = HashMap::new();
+    routes.insert(String::from("/ok"), ());
+    routes.insert(String::from("/:"), ());
+    routes.insert(String::from("/*/more"), ());
+    let errors = Router::try_from(routes).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("a wildcard must be the last segment"));
+    assert_eq!(errors[1], "params must be named");
+
+    Ok(())
+}
+
+#[test]
+fn matches_into_owned_vec() -> Result {
+    let mut router = Router::new();
+    router.add("/*", 1)?;
+    router.add("/users/:id", 2)?;
+    router.add("/users/me", 3)?;
+    router.add("/posts", 4)?;
+
+    let path = String::from("/users/me");
+    let matches: Vec<Match<'_, '_, i32>> = router.matches(&path).into_iter().collect();
+    assert_eq!(matches.len(), 3);
+    assert_eq!(
+        matches.iter().map(|m| *m.handler()).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert_eq!(matches[1].captures().get("id"), Some("me"));
+
+    let mut handlers = vec![];
+    for m in router.matches("/posts") {
+        handlers.push(*m.handler());
+    }
+    assert_eq!(handlers, vec![1, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn per_route_separator() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id", "path")?;
+    router.add_with_separator("users.:id.*", "dotted", '.')?;
+    router.add_with_separator("users.admin", "dotted admin", '.')?;
+
+    let m = router.best_match("/users/5").unwrap();
+    assert_eq!(*m.handler(), "path");
+    assert_eq!(m.captures().get("id"), Some("5"));
+
+    let m = router.best_match("users.5").unwrap();
+    assert_eq!(*m.handler(), "dotted");
+    assert_eq!(m.captures().get("id"), Some("5"));
+    assert_eq!(m.captures().wildcard(), Some(""));
+
+    let m = router.best_match("users.5.settings.email").unwrap();
+    assert_eq!(*m.handler(), "dotted");
+    assert_eq!(m.captures().wildcard(), Some("settings.email"));
+    assert_eq!(m.matched_prefix(), "users.5");
+    assert_eq!(m.canonical_path(), ".users.5.settings.email");
+    assert_eq!(m.captures().to_path().unwrap(), ".users.5.settings.email");
+
+    assert_eq!(
+        *router.best_match("users.admin").unwrap().handler(),
+        "dotted admin"
+    );
+    assert_eq!(
+        router
+            .best_match("/users/5.extra")
+            .unwrap()
+            .captures()
+            .get("id"),
+        Some("5.extra")
+    );
+    assert!(router.best_match("/users.5").is_none());
+
+    let dotted = RouteSpec::parse_with_separator("users.:id", '.')?;
+    let slashed: RouteSpec = "/users/:id".parse()?;
+    assert_eq!(dotted.segments(), slashed.segments());
+    assert_ne!(dotted, slashed);
+
+    Ok(())
+}
+
+#[test]
+fn param_positions() -> Result {
+    let spec: RouteSpec = "/a/:x/b/:y".parse()?;
+    assert_eq!(
+        spec.param_positions(),
+        vec![(String::from("x"), 2), (String::from("y"), 6)]
+    );
+
+    for (name, index) in spec.param_positions() {
+        assert_eq!(spec.segments()[index], Segment::Param(name.into()));
+    }
+
+    let spec: RouteSpec = "/a/b/*".parse()?;
+    assert!(spec.param_positions().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn escaped_specs_round_trip() -> Result {
+    let spec: RouteSpec = r"/files/\:id/\*/a\\b/x:y*".parse()?;
+    assert_eq!(
+        spec.segments(),
+        &[
+            Segment::Exact("files".into()),
+            Segment::Slash,
+            Segment::Exact(":id".into()),
+            Segment::Slash,
+            Segment::Exact("*".into()),
+            Segment::Slash,
+            Segment::Exact(r"a\b".into()),
+            Segment::Slash,
+            Segment::Exact("x:y*".into()),
+        ][..]
+    );
+
+    let rendered = spec.to_string();
+    assert_eq!(rendered, r"/files/\:id/\*/a\\b/x\:y\*");
+    assert_eq!(rendered.parse::<RouteSpec>()?, spec);
+
+    let mut router = Router::new();
+    router.add(r"/\:id", 1)?;
+    router.add("/:id", 2)?;
+    assert_eq!(router.best_match_copied("/:id"), Some(1));
+    assert_eq!(router.best_match_copied("/5"), Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn wildcard_segments() -> Result {
+    let mut router = Router::new();
+    router.add("/files/*", ())?;
+    router.add("/users/:id", ())?;
+    router.add_with_separator("events.*", (), '.')?;
+
+    let m = router.best_match("/files/a/b/c").unwrap();
+    assert_eq!(m.wildcard_segments(), Some(vec!["a", "b", "c"]));
+
+    let m = router.best_match("/files/a/b/c/").unwrap();
+    assert_eq!(m.wildcard_segments(), Some(vec!["a", "b", "c"]));
+
+    let m = router.best_match("/files").unwrap();
+    assert_eq!(m.wildcard_segments(), Some(vec![]));
+
+    let m = router.best_match("events.user.signup").unwrap();
+    assert_eq!(m.wildcard_segments(), Some(vec!["user", "signup"]));
+
+    let m = router.best_match("/users/1").unwrap();
+    assert_eq!(m.wildcard_segments(), None);
+
+    Ok(())
+}
+
+#[test]
+fn root_route() -> Result {
+    let mut router = Router::new();
+    router.add("/", "root")?;
+    assert!(router.is_match("/"));
+    assert!(router.is_match(""));
+    assert!(!router.is_match("/x"));
+    assert!(!router.is_match("/x/y"));
+    assert!(router.best_match("/x").is_none());
+
+    router.add("*", "catch-all")?;
+    assert_eq!(*router.best_match("/").unwrap().handler(), "root");
+    assert_eq!(*router.best_match("").unwrap().handler(), "root");
+    assert_eq!(*router.best_match("/x").unwrap().handler(), "catch-all");
+    assert_eq!(router.matches("/").len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn is_exact_match() -> Result {
+    let mut router = Router::new();
+    router.set_case_insensitive(true);
+    router.add("/", ())?;
+    router.add("/users/list", ())?;
+    router.add("/users/:id", ())?;
+    router.add("/files/*", ())?;
+
+    assert!(router.best_match("/").unwrap().is_exact_match());
+    assert!(router.best_match("/users/list").unwrap().is_exact_match());
+    assert!(router.best_match("/users/list/").unwrap().is_exact_match());
+    assert!(router.best_match("users/list").unwrap().is_exact_match());
+    assert!(router
+        .best_match("//users/list//")
+        .unwrap()
+        .is_exact_match());
+    assert!(!router.best_match("/Users/List").unwrap().is_exact_match());
+    assert!(!router.best_match("/users/5").unwrap().is_exact_match());
+    assert!(!router.best_match("/files").unwrap().is_exact_match());
+
+    let mut router = Router::new();
+    router.set_base_path("/api/:version")?;
+    router.add("/status", ())?;
+    assert!(!router
+        .best_match("/api/v1/status")
+        .unwrap()
+        .is_exact_match());
+
+    Ok(())
+}
+
+#[test]
+fn insertion_order_precedence() -> Result {
+    let mut router = Router::new();
+    router.add("/users/*", "wildcard")?;
+    router.add("/users/:id", "param")?;
+    router.add("/users/me", "exact")?;
+    router.add("/users/:name", "second param")?;
+
+    assert_eq!(router.precedence(), Precedence::Structural);
+    assert_eq!(*router.best_match("/users/me").unwrap().handler(), "exact");
+    assert_eq!(*router.best_match("/users/5").unwrap().handler(), "param");
+
+    router.set_precedence(Precedence::InsertionOrder);
+    assert_eq!(
+        *router.best_match("/users/me").unwrap().handler(),
+        "wildcard"
+    );
+    assert_eq!(
+        *router.best_match("/users/5").unwrap().handler(),
+        "wildcard"
+    );
+
+    router.add("/posts/:id", "posts param")?;
+    router.add("/posts/new", "posts exact")?;
+    assert_eq!(
+        *router.best_match("/posts/new").unwrap().handler(),
+        "posts param"
+    );
+
+    router.set_precedence(Precedence::Structural);
+    assert_eq!(*router.best_match("/users/me").unwrap().handler(), "exact");
+    assert_eq!(*router.best_match("/users/5").unwrap().handler(), "param");
+    assert_eq!(
+        *router.best_match("/posts/new").unwrap().handler(),
+        "posts exact"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn matches_exactly() -> Result {
+    let mut router = Router::new();
+    router.add("/users/list", ())?;
+    router.add("/users/:id", ())?;
+
+    let exact = router.best_match("/users/list").unwrap().route();
+    assert!(exact.matches_exactly("/users/list"));
+    assert!(!exact.matches_exactly("/users/other"));
+
+    let param = router.best_match("/users/5").unwrap().route();
+    assert!(!param.matches_exactly("/users/5"));
+    assert!(!param.matches_exactly("/users/:id"));
+    assert!(!param.matches_exactly("/users/list"));
+
+    Ok(())
+}
+
+#[test]
+fn try_capture_from_spec() -> Result {
+    let spec: RouteSpec = "/:owner/:repo/blob/*".parse()?;
+    let captures = spec
+        .try_capture("/hactar/routefinder/blob/src/lib.rs")
+        .unwrap();
+    assert_eq!(captures.get("owner"), Some("hactar"));
+    assert_eq!(captures.get("repo"), Some("routefinder"));
+    assert_eq!(captures.wildcard(), Some("src/lib.rs"));
+    assert_eq!(captures.spec(), Some(&spec));
+
+    let spec: RouteSpec = "/about".parse()?;
+    let captures = spec.try_capture("/about/").unwrap();
+    assert!(captures.is_empty());
+    assert_eq!(captures.wildcard(), None);
+
+    assert!(spec.try_capture("/contact").is_none());
+    assert!("/users/:id"
+        .parse::<RouteSpec>()?
+        .try_capture("/users//")
+        .is_none());
+
+    Ok(())
+}
+
+#[test]
+fn wildcard_outranks_param() -> Result {
+    let mut router = Router::new();
+    router.add("/users/*", "wildcard")?;
+    router.add("/users/:id", "param")?;
+    router.add("/users/me", "exact")?;
+
+    assert_eq!(*router.best_match("/users/5").unwrap().handler(), "param");
+
+    router.set_wildcard_outranks_param(true);
+    assert_eq!(
+        *router.best_match("/users/5").unwrap().handler(),
+        "wildcard"
+    );
+    assert_eq!(*router.best_match("/users/me").unwrap().handler(), "exact");
+
+    router.add("/posts/:id", "posts param")?;
+    router.add("/posts/*", "posts wildcard")?;
+    assert_eq!(
+        *router.best_match("/posts/5").unwrap().handler(),
+        "posts wildcard"
+    );
+
+    router.set_wildcard_outranks_param(false);
+    assert_eq!(*router.best_match("/users/5").unwrap().handler(), "param");
+    assert_eq!(
+        *router.best_match("/posts/5").unwrap().handler(),
+        "posts param"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn swap_handlers() -> Result {
+    let mut router = Router::new();
+    router.add("/x", "x")?;
+    router.add("/y", "y")?;
+    router.add("/users/:id", "user")?;
+
+    router.swap_handlers("/x", "/y")?;
+    assert_eq!(*router.best_match("/x").unwrap().handler(), "y");
+    assert_eq!(*router.best_match("/y").unwrap().handler(), "x");
+
+    router.swap_handlers("/y", "/users/:id")?;
+    assert_eq!(*router.best_match("/y").unwrap().handler(), "user");
+    assert_eq!(*router.best_match("/users/1").unwrap().handler(), "x");
+
+    router.swap_handlers("/x", "/x")?;
+    assert_eq!(*router.best_match("/x").unwrap().handler(), "y");
+
+    assert!(router.swap_handlers("/x", "/z").is_err());
+    assert!(router.swap_handlers("/x", "/:").is_err());
+    assert_eq!(*router.best_match("/x").unwrap().handler(), "y");
+
+    Ok(())
+}
+
+#[test]
+fn named_captures_match_owned_captures() -> Result {
+    let mut router = Router::new();
+    router.set_base_path("/:tenant")?;
+    router.add("/:owner/:repo/*", ())?;
+
+    let m = router
+        .best_match("/acme/hactar/routefinder/src/lib.rs")
+        .unwrap();
+    let named: Vec<(&str, &str)> = m.named_captures().collect();
+    assert_eq!(
+        named,
+        vec![
+            ("tenant", "acme"),
+            ("owner", "hactar"),
+            ("repo", "routefinder")
+        ]
+    );
+
+    let captures = m.captures();
+    let owned: Vec<(&str, &str)> = captures
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(named, owned);
+
+    Ok(())
+}
+
+#[test]
+fn remap_prefix() -> Result {
+    let mut router = Router::new();
+    router.add("/v1", "root")?;
+    router.add("/v1/users/:id", "user")?;
+    router.add("/v1/*", "fallback")?;
+    router.add("/v10/users", "v10")?;
+    router.add("/users", "unversioned")?;
+
+    assert_eq!(router.remap_prefix("/v1", "/v2")?, 3);
+
+    assert_eq!(*router.best_match("/v2").unwrap().handler(), "root");
+    assert_eq!(*router.best_match("/v2/users/1").unwrap().handler(), "user");
+    assert_eq!(
+        *router.best_match("/v2/other").unwrap().handler(),
+        "fallback"
+    );
+    assert!(router.best_match("/v1").is_none());
+    assert!(router.best_match("/v1/users/1").is_none());
+    assert_eq!(*router.best_match("/v10/users").unwrap().handler(), "v10");
+    assert_eq!(
+        *router.best_match("/users").unwrap().handler(),
+        "unversioned"
+    );
+
+    router.add("/v3/users/:id", "v3 user")?;
+    assert!(router.remap_prefix("/v2", "/v3").is_err());
+    assert_eq!(*router.best_match("/v2/users/1").unwrap().handler(), "user");
+
+    assert_eq!(router.remap_prefix("/v2", "/api/v2")?, 3);
+    assert_eq!(
+        *router.best_match("/api/v2/users/1").unwrap().handler(),
+        "user"
+    );
+    assert!(router.remap_prefix("/*", "/v4").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn same_route() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id", ())?;
+    router.add("/users/:name", ())?;
+    router.add("/admin", ())?;
+
+    assert!(router.same_route("/users/5", "/users/6"));
+    assert!(router.same_route("/admin", "/admin/"));
+    assert!(!router.same_route("/users/5", "/admin"));
+    assert!(!router.same_route("/missing", "/missing"));
+
+    Ok(())
+}
+
+#[test]
+fn extract_from_captures() -> Result {
+    #[derive(Debug)]
+    struct Comment {
+        post: u32,
+        comment: u64,
+    }
+
+    impl FromCaptures for Comment {
+        fn from_captures(captures: &Captures) -> std::result::Result<Self, ExtractError> {
+            Ok(Self {
+                post: captures.parse("post")?,
+                comment: captures.parse("comment")?,
+            })
+        }
+    }
+
+    let mut router = Router::new();
+    router.add("/posts/:post/comments/:comment", ())?;
+    router.add("/posts/:post", ())?;
+
+    let comment: Comment = router
+        .best_match("/posts/1/comments/2")
+        .unwrap()
+        .extract()?;
+    assert_eq!(comment.post, 1);
+    assert_eq!(comment.comment, 2);
+
+    let missing = router.best_match("/posts/1").unwrap().extract::<Comment>();
+    assert_eq!(
+        missing.unwrap_err(),
+        ExtractError::Missing {
+            name: String::from("comment")
+        }
+    );
+
+    let invalid = router
+        .best_match("/posts/first/comments/2")
+        .unwrap()
+        .extract::<Comment>()
+        .unwrap_err();
+    assert_eq!(
+        invalid,
+        ExtractError::Parse {
+            name: String::from("post"),
+            value: String::from("first"),
+            expected: "u32"
+        }
+    );
+    assert_eq!(
+        invalid.to_string(),
+        "could not parse param `post` value `first` as u32"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn named_wildcard_segments() -> Result {
+    let mut router = Router::new();
+    router.add("/browse/*parts", ())?;
+    router.add("/*", ())?;
+
+    let captures = router.best_match("/browse/a/b/c").unwrap().captures();
+    assert_eq!(captures.get_segments("parts"), Some(vec!["a", "b", "c"]));
+    assert_eq!(captures.wildcard(), Some("a/b/c"));
+    assert_eq!(captures.get_segments("other"), None);
+    assert_eq!(captures.to_path().unwrap(), "/browse/a/b/c");
+
+    let captures = router.best_match("/browse").unwrap().captures();
+    assert_eq!(captures.get_segments("parts"), Some(vec![]));
+
+    let captures = router.best_match("/a/b").unwrap().captures();
+    assert_eq!(captures.get_segments("parts"), None);
+
+    let spec: RouteSpec = "/browse/*parts".parse()?;
+    assert_eq!(spec.wildcard_name(), Some("parts"));
+    assert_eq!(spec.to_string(), "/browse/*parts");
+    assert_ne!(spec, "/browse/*".parse()?);
+
+    Ok(())
+}
+
+#[test]
+fn typed_routes() -> Result {
+    let route = typed_route!("/users/:id/files/:name/*" as (u64, String))?;
+    let (id, name): (u64, String) = route.is_match("/users/5/files/notes/a/b").unwrap();
+    assert_eq!(id, 5);
+    assert_eq!(name, "notes");
+    assert_eq!(route.is_match("/users/five/files/notes"), None);
+    assert_eq!(route.is_match("/posts/5"), None);
+
+    let route = typed_route!("/health" as ())?;
+    assert_eq!(route.is_match("/health"), Some(()));
+
+    let error = TypedRoute::<(u64, u64)>::new("/users/:id").unwrap_err();
+    assert_eq!(error, "`/users/:id` has 1 params, but its type has 2");
+
+    let route = typed_route!(r"/a\:b/:id" as (u64))?;
+    assert_eq!(route.is_match("/a:b/5"), Some((5,)));
+
+    Ok(())
+}
+
+#[test]
+fn typed_best_match() -> Result {
+    let mut router = Router::new();
+    router.set_base_path("/:tenant")?;
+    router.add_typed(typed_route!("/users/:id" as (u64))?, "by id");
+    router.add_typed(typed_route!("/users/:name" as (String))?, "by name");
+    router.add("/users/*", "wildcard")?;
+
+    let (m, (id,)) = router.best_match_typed::<(u64,)>("/acme/users/5").unwrap();
+    assert_eq!(*m.handler(), "by id");
+    assert_eq!(id, 5);
+    assert_eq!(m.captures().get("tenant"), Some("acme"));
+
+    let (m, (name,)) = router
+        .best_match_typed::<(String,)>("/acme/users/jane")
+        .unwrap();
+    assert_eq!(*m.handler(), "by name");
+    assert_eq!(name, "jane");
+    assert_eq!(
+        *router.best_match("/acme/users/jane").unwrap().handler(),
+        "by name"
+    );
+    assert_eq!(
+        *router.best_match("/acme/users/5").unwrap().handler(),
+        "by id"
+    );
+    assert_eq!(router.matches("/acme/users/jane").len(), 2);
+    assert!(router.is_match("/acme/users/jane"));
+
+    assert!(router
+        .best_match_typed::<(u64,)>("/acme/users/jane")
+        .is_none());
+    let (m, ()) = router.best_match_typed::<()>("/acme/users/jane").unwrap();
+    assert_eq!(*m.handler(), "wildcard");
+
+    let mut ids = Router::new();
+    ids.add_typed(typed_route!("/users/:id" as (u64))?, ());
+    assert!(ids.is_match("/users/5"));
+    assert!(!ids.is_match("/users/jane"));
+    assert!(ids.matches("/users/jane").is_empty());
+    assert!(ids.best_match_typed::<(String,)>("/users/jane").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn match_result() -> Result {
+    let mut router = Router::new();
+    router.set_base_path("/:tenant")?;
+    router.add_typed(typed_route!("/users/:id/posts/:n" as (u64, u8))?, "post");
+    router.add_typed(typed_route!("/users/:id" as (u64))?, "user");
+    router.add("/about", "about")?;
+
+    match router.match_result("/acme/users/5/posts/300") {
+        MatchResult::PathKnownButRejected(reason) => assert_eq!(
+            reason,
+            RejectionReason {
+                param: "n",
+                value: "300",
+                constraint: "u8"
+            }
+        ),
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+
+    match router.match_result("/acme/users/jane") {
+        MatchResult::PathKnownButRejected(reason) => {
+            assert_eq!(reason.param, "id");
+            assert_eq!(reason.value, "jane");
+            assert_eq!(reason.constraint, "u64");
+        }
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+
+    match router.match_result("/acme/users/5") {
+        MatchResult::Matched(m) => assert_eq!(*m.handler(), "user"),
+        other => panic!("expected a match, got {:?}", other),
+    }
+    assert!(matches!(
+        router.match_result("/acme/about"),
+        MatchResult::Matched(_)
+    ));
+    assert!(matches!(
+        router.match_result("/acme/posts/5"),
+        MatchResult::NotFound
+    ));
+
+    router.add("/users/*", "wildcard")?;
+    match router.match_result("/acme/users/jane") {
+        MatchResult::Matched(m) => assert_eq!(*m.handler(), "wildcard"),
+        other => panic!("expected a match, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn match_metrics() -> Result {
+    use std::sync::{Arc, Mutex};
+
+    let mut router = Router::new();
+    router.add("/*", ())?;
+    router.add("/users/:id", ())?;
+    router.add("/users/me", ())?;
+    router.add("/about", ())?;
+
+    let reported = Arc::new(Mutex::new(vec![]));
+    let sink = reported.clone();
+    router.set_metrics(move |metrics| sink.lock().unwrap().push(metrics));
+
+    router.best_match("/users/5");
+    router.best_match("/users/me");
+    router.best_match("/anything/else");
+
+    let mut empty = Router::<()>::new();
+    let sink = reported.clone();
+    empty.set_metrics(move |metrics| sink.lock().unwrap().push(metrics));
+    empty.best_match("/missing");
+
+    assert_eq!(
+        *reported.lock().unwrap(),
+        vec![
+            MatchMetrics {
+                path_len: 8,
+                routes_scanned: 2,
+                matched: true
+            },
+            // found by direct lookup, without scanning
+            MatchMetrics {
+                path_len: 9,
+                routes_scanned: 0,
+                matched: true
+            },
+            MatchMetrics {
+                path_len: 14,
+                routes_scanned: 3,
+                matched: true
+            },
+            MatchMetrics {
+                path_len: 8,
+                routes_scanned: 0,
+                matched: false
+            },
+        ]
+    );
+
+    let mut commands = Router::new();
+    commands.add("/deploy", ())?;
+    let sink = reported.clone();
+    commands.set_metrics(move |metrics| sink.lock().unwrap().push(metrics));
+    reported.lock().unwrap().clear();
+    commands.best_partial("/deploy a b c d");
+    assert_eq!(
+        *reported.lock().unwrap(),
+        vec![MatchMetrics {
+            path_len: 15,
+            routes_scanned: 4,
+            matched: true
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn best_partial_with_trailing_args() -> Result {
+    let mut router = Router::new();
+    router.add("/deploy", "deploy")?;
+    router.add("/deploy/:env", "deploy env")?;
+    router.add("/status", "status")?;
+
+    let (m, rest) = router.best_partial("/deploy prod --force").unwrap();
+    assert_eq!(*m.handler(), "deploy");
+    assert_eq!(rest, "prod --force");
+
+    let (m, rest) = router.best_partial("/deploy/prod --force").unwrap();
+    assert_eq!(*m.handler(), "deploy env");
+    assert_eq!(m.captures().get("env"), Some("prod --force"));
+    assert_eq!(rest, "");
+
+    let (m, rest) = router.best_partial("/status").unwrap();
+    assert_eq!(*m.handler(), "status");
+    assert_eq!(rest, "");
+
+    let (m, rest) = router.best_partial("/status/a/b").unwrap();
+    assert_eq!(*m.handler(), "status");
+    assert_eq!(rest, "a/b");
+
+    assert!(router.best_partial("/unknown prod").is_none());
+    assert!(router.best_partial("/deployment").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn remove_and_retain_captures() -> Result {
+    let mut router = Router::new();
+    router.add("/:_internal/:org/:repo/*", ())?;
+
+    let mut captures = router
+        .best_match("/x/hactar/routefinder/src")
+        .unwrap()
+        .captures();
+    assert_eq!(captures.remove("org"), Some(String::from("hactar")));
+    assert_eq!(captures.remove("org"), None);
+    assert_eq!(captures.get("repo"), Some("routefinder"));
+    assert_eq!(captures.len(), 2);
+
+    let mut captures = router
+        .best_match("/x/hactar/routefinder/src")
+        .unwrap()
+        .captures();
+    captures.retain(|name, value| !name.starts_with('_') && value != "routefinder");
+    let remaining: Vec<_> = captures
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(remaining, vec![("org", "hactar")]);
+    assert_eq!(captures.wildcard(), Some("src"));
+
+    Ok(())
+}
+
+#[test]
+fn trailing_param_captures_exactly_one_segment() -> Result {
+    let mut param = Router::new();
+    param.add("/files/:name", ())?;
+    assert_eq!(
+        param.best_match("/files/a").unwrap().captures().get("name"),
+        Some("a")
+    );
+    assert!(param.best_match("/files/a/").is_some());
+    assert!(param.best_match("/files/a/b").is_none());
+    assert!(param.best_match("/files").is_none());
+
+    let mut wildcard = Router::new();
+    wildcard.add("/files/*", ())?;
+    assert_eq!(
+        wildcard
+            .best_match("/files/a/b")
+            .unwrap()
+            .captures()
+            .wildcard(),
+        Some("a/b")
+    );
+    assert_eq!(
+        wildcard.best_match("/files").unwrap().captures().wildcard(),
+        Some("")
+    );
+
+    let mut both = Router::new();
+    both.add("/files/:name", "param")?;
+    both.add("/files/*", "wildcard")?;
+    assert_eq!(*both.best_match("/files/a").unwrap().handler(), "param");
+    assert_eq!(
+        *both.best_match("/files/a/b").unwrap().handler(),
+        "wildcard"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn static_and_dynamic_specs() -> Result {
+    use std::borrow::Cow;
+    use std::convert::TryFrom;
+
+    let spec = RouteSpec::from_static("/users/:id")?;
+    assert!(matches!(
+        &spec.segments()[0],
+        Segment::Exact(Cow::Borrowed("users"))
+    ));
+    assert!(matches!(
+        &spec.segments()[2],
+        Segment::Param(Cow::Borrowed("id"))
+    ));
+
+    let escaped = RouteSpec::from_static(r"/a\:b")?;
+    assert!(matches!(&escaped.segments()[0], Segment::Exact(Cow::Owned(e)) if e == "a:b"));
+
+    let id = 5;
+    let owned = RouteSpec::try_from("/users/:id")?;
+    assert!(matches!(&owned.segments()[0], Segment::Exact(Cow::Owned(e)) if e == "users"));
+    assert_eq!(owned, spec);
+
+    let borrowed = RouteSpec::try_from(Cow::Borrowed("/users/:id"))?;
+    assert!(matches!(
+        &borrowed.segments()[0],
+        Segment::Exact(Cow::Borrowed("users"))
+    ));
+
+    let dynamic = RouteSpec::try_from(format!("/users{}/:id", id))?;
+    assert!(matches!(&dynamic.segments()[0], Segment::Exact(Cow::Owned(e)) if e == "users5"));
+    assert_eq!(dynamic.source(), "/users5/:id");
+
+    let mut router = Router::new();
+    router.add(format!("/teams/{}/:member", id), "dynamic")?;
+    router.add(RouteSpec::from_static("/teams/:team/:member")?, "static")?;
+    let local = String::from("/local/:name");
+    router.add(local.as_str(), "parsed")?;
+
+    let m = router.best_match("/teams/5/jane").unwrap();
+    assert_eq!(*m.handler(), "dynamic");
+    assert_eq!(m.captures().get("member"), Some("jane"));
+    assert_eq!(
+        *router.best_match("/teams/6/jane").unwrap().handler(),
+        "static"
+    );
+    assert_eq!(*router.best_match("/local/x").unwrap().handler(), "parsed");
+
+    Ok(())
+}
+
+#[test]
+fn best_match_into_reuses_output() -> Result {
+    #[derive(Default)]
+    struct Path {
+        user: String,
+        post: u32,
+    }
+
+    impl FromCaptures for Path {
+        fn from_captures(captures: &Captures) -> std::result::Result<Self, ExtractError> {
+            Ok(Self {
+                user: captures.parse("user")?,
+                post: captures.parse("post")?,
+            })
+        }
+
+        fn from_match_into<T>(
+            m: &Match<'_, '_, T>,
+            out: &mut Self,
+        ) -> std::result::Result<(), ExtractError> {
+            for (name, value) in m.named_captures() {
+                match name {
+                    "user" => {
+                        out.user.clear();
+                        out.user.push_str(value);
+                    }
+                    "post" => {
+                        out.post = value.parse().map_err(|_| ExtractError::Parse {
+                            name: String::from(name),
+                            value: String::from(value),
+                            expected: "u32",
+                        })?
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let mut router = Router::new();
+    router.add("/:user/posts/:post", "post")?;
+
+    let mut out = Path::default();
+    out.user.reserve(64);
+    let capacity = out.user.capacity();
+
+    assert_eq!(
+        router.best_match_into("/jane/posts/1", &mut out),
+        Some(&"post")
+    );
+    assert_eq!((out.user.as_str(), out.post), ("jane", 1));
+
+    assert_eq!(
+        router.best_match_into("/john/posts/2", &mut out),
+        Some(&"post")
+    );
+    assert_eq!((out.user.as_str(), out.post), ("john", 2));
+    assert_eq!(out.user.capacity(), capacity);
+
+    assert_eq!(router.best_match_into("/john/posts/x", &mut out), None);
+    assert_eq!(router.best_match_into("/missing", &mut out), None);
+    assert_eq!((out.user.as_str(), out.post), ("john", 2));
+
+    router.add("/files/*", "files")?;
+    let mut captures = Captures::default();
+    router.best_match_into("/jane/posts/1", &mut captures);
+    assert_eq!(captures.get("user"), Some("jane"));
+    assert_eq!(captures.wildcard(), None);
+    router.best_match_into("/files/a/b", &mut captures);
+    assert_eq!(captures.get("user"), None);
+    assert_eq!(captures.wildcard(), Some("a/b"));
+    assert_eq!(captures.to_path().unwrap(), "/files/a/b");
+
+    Ok(())
+}
+
+#[test]
+fn partial_matches_are_not_fully_consumed() -> Result {
+    let mut router = Router::new();
+    router.add("/deploy", ())?;
+
+    let (m, rest) = router.best_partial("/deploy prod --force").unwrap();
+    assert_eq!(rest, "prod --force");
+    assert!(!m.fully_consumed());
+
+    let (m, rest) = router.best_partial("/deploy/").unwrap();
+    assert_eq!(rest, "");
+    assert!(m.fully_consumed());
+
+    assert!(router.best_match("/deploy").unwrap().fully_consumed());
+    assert!(router.matches("/deploy").iter().all(|m| m.fully_consumed()));
+
+    Ok(())
+}
+
+#[test]
+fn custom_hasher_for_static_routes() -> Result {
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    #[derive(Default)]
+    struct Fnv(u64);
+
+    impl Hasher for Fnv {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100_0000_01b3);
+            }
+        }
+    }
+
+    let mut router = Router::with_hasher(BuildHasherDefault::<Fnv>::default());
+    router.add("/", "root")?;
+    router.add("/about", "about")?;
+    router.add("/about/team", "team")?;
+    router.add("/:page", "page")?;
+    router.add("/*", "wildcard")?;
+    router.entry("/contact")?.or_insert("contact");
+
+    assert_eq!(*router.best_match("/").unwrap().handler(), "root");
+    assert_eq!(*router.best_match("/about/").unwrap().handler(), "about");
+    assert_eq!(*router.best_match("/about/team").unwrap().handler(), "team");
+    assert_eq!(*router.best_match("/contact").unwrap().handler(), "contact");
+    assert_eq!(*router.best_match("/blog").unwrap().handler(), "page");
+    assert_eq!(
+        *router.best_match("/blog/post").unwrap().handler(),
+        "wildcard"
+    );
+    assert!(router.is_match("/about"));
+
+    router.set_precedence(Precedence::InsertionOrder);
+    assert_eq!(*router.best_match("/about").unwrap().handler(), "about");
+    assert_eq!(*router.best_match("/contact").unwrap().handler(), "page");
+
+    router.set_precedence(Precedence::Structural);
+    router.set_case_insensitive(true);
+    assert_eq!(*router.best_match("/ABOUT").unwrap().handler(), "about");
+
+    Ok(())
+}
+
+#[test]
+fn rename_param() -> Result {
+    let mut route = Route::new("/users/:id/posts/:post", ())?;
+    let before = route.is_match("/users/5/posts/1").unwrap().captures();
+    assert!(route.rename_param("id", "user_id"));
+    assert!(!route.rename_param("missing", "other"));
+    assert_eq!(before.spec().unwrap().to_string(), "/users/:id/posts/:post");
+
+    let captures = route.is_match("/users/5/posts/1").unwrap().captures();
+    assert!(std::ptr::eq(captures.spec().unwrap(), route.definition()));
+    assert_eq!(captures.get("user_id"), Some("5"));
+    assert_eq!(captures.get("id"), None);
+    assert_eq!(captures.get("post"), Some("1"));
+    assert_eq!(route.definition().source(), "/users/:user_id/posts/:post");
+    assert_eq!(route.definition(), &"/users/:user_id/posts/:post".parse()?);
+
+    Ok(())
+}
+
+#[test]
+fn best_match_batch_equals_best_match() -> Result {
+    let mut router = Router::new();
+    router.add("/", 0)?;
+    router.add("/about", 1)?;
+    router.add("/users/:id", 2)?;
+    router.add("/users/me", 3)?;
+    router.add("/files/*", 4)?;
+
+    let paths = [
+        "/",
+        "/about",
+        "/users/5",
+        "/users/me",
+        "/files/a/b",
+        "/missing",
+        "/users/5/extra",
+    ];
+
+    let handlers = |matches: Vec<Option<Match<'_, '_, usize>>>| -> Vec<Option<usize>> {
+        matches
+            .into_iter()
+            .map(|m| m.map(|m| *m.handler()))
+            .collect()
+    };
+
+    let individually: Vec<_> = paths.iter().map(|path| router.best_match(path)).collect();
+    let expected = vec![Some(0), Some(1), Some(2), Some(3), Some(4), None, None];
+    assert_eq!(handlers(individually), expected);
+    assert_eq!(handlers(router.best_match_batch(&paths)), expected);
+
+    router.set_case_insensitive(true);
+    assert_eq!(
+        handlers(router.best_match_batch(&["/ABOUT", "/Users/Me"])),
+        vec![Some(1), Some(3)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn clear_and_reserve_rebuild() -> Result {
+    let mut router = Router::new();
+    router.set_base_path("/api")?;
+
+    for generation in 0..3 {
+        router.clear_and_reserve(3);
+        router.add(format!("/v{}/users/:id", generation), generation)?;
+        router.add(format!("/v{}/status", generation), generation)?;
+        router.add_with_separator("events.*", generation, '.')?;
+
+        let status = format!("/api/v{}/status", generation);
+        assert_eq!(router.best_match_copied(&status), Some(generation));
+        assert_eq!(
+            router.best_match_copied(&format!("/api/v{}/users/5", generation)),
+            Some(generation)
+        );
+
+        if generation > 0 {
+            assert!(!router.is_match(&format!("/api/v{}/status", generation - 1)));
+        }
+    }
+
+    router.clear_and_reserve(0);
+    assert!(router.best_match("/api/v2/status").is_none());
+    router.add("/status", 10)?;
+    assert_eq!(router.best_match_copied("/api/status"), Some(10));
+
+    Ok(())
+}
+
+#[test]
+fn deprecated_routes() -> Result {
+    let mut router = Router::new();
+    router.add_deprecated("/users/:id", "old")?;
+    router.add("/users/me", "me")?;
+    router.add("/*", "fallback")?;
+
+    let m = router.best_match("/users/5").unwrap();
+    assert_eq!(*m.handler(), "old");
+    assert!(m.is_deprecated());
+    assert!(m.route().is_deprecated());
+
+    assert!(!router.best_match("/users/me").unwrap().is_deprecated());
+    assert!(!router.best_match("/other").unwrap().is_deprecated());
+    assert_eq!(
+        router
+            .matches("/users/5")
+            .iter()
+            .filter(|m| m.is_deprecated())
+            .count(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn routes_accepting_param_names() -> Result {
+    let mut router = Router::new();
+    router.add("/users/:id", ())?;
+    router.add("/users/:id/posts/:pid", ())?;
+    router.add("/about", ())?;
+    router.add("/files/*", ())?;
+
+    let specs = |routes: Vec<&Route<()>>| -> Vec<String> {
+        routes.iter().map(|r| r.definition().to_string()).collect()
+    };
+
+    assert_eq!(
+        specs(router.routes_accepting(&["id"])),
+        vec!["/about", "/users/:id", "/files/*"]
+    );
+    assert_eq!(
+        specs(router.routes_accepting(&["pid", "id", "unused"])),
+        vec!["/about", "/users/:id", "/users/:id/posts/:pid", "/files/*"]
+    );
+    assert_eq!(
+        specs(router.routes_accepting(&[])),
+        vec!["/about", "/files/*"]
+    );
+
+    router.set_base_path("/:tenant")?;
+    assert!(router.routes_accepting(&["id"]).is_empty());
+    assert_eq!(router.routes_accepting(&["tenant", "id"]).len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn visit_matches_stops_early() -> Result {
+    use std::ops::ControlFlow;
+
+    let mut router = Router::new();
+    router.add("/*", "wildcard")?;
+    router.add("/users/:id", "param")?;
+    router.add("/users/me", "exact")?;
+    router.add("/users/:name", "second param")?;
+
+    let mut visited = vec![];
+    router.visit_matches("/users/me", |m| {
+        visited.push(*m.handler());
+        if m.captures().get("id").is_some() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(visited, vec!["exact", "param"]);
+
+    let mut visited = vec![];
+    router.visit_matches("/users/5", |m| {
+        visited.push(*m.handler());
+        ControlFlow::Continue(())
+    });
+    assert_eq!(visited, vec!["param", "second param", "wildcard"]);
+
+    let mut visited = 0;
+    router.visit_matches("/", |_| {
+        visited += 1;
+        ControlFlow::Continue(())
+    });
+    assert_eq!(visited, 1);
+
+    Ok(())
+}